@@ -118,7 +118,7 @@ private fun ContentType.matches(type: String) = this.getBaseType().orEmpty().mat
  * Type of entry in the catalogue
  */
 enum class CatalogueEntryType {
-  CONTENT_MATCHER, CONTENT_GENERATOR, MOCK_SERVER, MATCHER, INTERACTION;
+  CONTENT_MATCHER, CONTENT_GENERATOR, MOCK_SERVER, MATCHER, INTERACTION, GENERATOR;
 
   override fun toString(): String {
     return when (this) {
@@ -127,6 +127,7 @@ enum class CatalogueEntryType {
       MOCK_SERVER -> "mock-server"
       MATCHER -> "matcher"
       INTERACTION -> "interaction"
+      GENERATOR -> "generator"
     }
   }
 
@@ -140,6 +141,7 @@ enum class CatalogueEntryType {
       MOCK_SERVER -> Plugin.CatalogueEntry.EntryType.MOCK_SERVER
       MATCHER -> Plugin.CatalogueEntry.EntryType.MATCHER
       INTERACTION -> Plugin.CatalogueEntry.EntryType.INTERACTION
+      GENERATOR -> Plugin.CatalogueEntry.EntryType.GENERATOR
     }
   }
 
@@ -152,6 +154,7 @@ enum class CatalogueEntryType {
         "content-matcher" -> CONTENT_MATCHER
         "content-generator" -> CONTENT_GENERATOR
         "interaction" -> INTERACTION
+        "generator" -> GENERATOR
         "matcher" -> MATCHER
         "mock-server" -> MOCK_SERVER
         else -> throw IllegalArgumentException("'$type' is not a valid CatalogueEntryType value")
@@ -169,6 +172,7 @@ enum class CatalogueEntryType {
           Plugin.CatalogueEntry.EntryType.MOCK_SERVER -> MOCK_SERVER
           Plugin.CatalogueEntry.EntryType.MATCHER -> MATCHER
           Plugin.CatalogueEntry.EntryType.INTERACTION -> INTERACTION
+          Plugin.CatalogueEntry.EntryType.GENERATOR -> GENERATOR
           Plugin.CatalogueEntry.EntryType.UNRECOGNIZED -> CONTENT_MATCHER
         }
       } else {
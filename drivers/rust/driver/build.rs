@@ -1,4 +1,12 @@
+use std::env;
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-  tonic_build::compile_protos("./plugin.proto")?;
+  // The descriptors are used to convert the messages to and from the Protobuf JSON format (see the
+  // `proto_json` module)
+  let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+  tonic_build::configure()
+    .file_descriptor_set_path(out_dir.join("plugin_descriptor.bin"))
+    .compile(&["./plugin.proto"], &["."])?;
   Ok(())
 }
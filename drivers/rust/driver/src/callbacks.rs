@@ -0,0 +1,319 @@
+//! Callback service that plugins can use to call back into the Pact implementation (the host).
+//! Plugins can query the catalogue, delegate matching to the core framework or another plugin, and
+//! get the values from provider states, rather than having to reimplement them.
+//!
+//! The service is opt-in: it is only started once the host has registered a `CallbackHandler` with
+//! `set_callback_handler`, the first time a plugin is loaded after that. Its address and a token
+//! for the session are passed to the plugin in the `InitPluginRequest`, and every call to the
+//! service must send the token as the `pact-callback-token` metadata entry. The service runs on
+//! its own thread, so plugins can call back into the host while the host is waiting on a request
+//! to the plugin.
+//!
+//! Matching with the core framework is done by the registered `CallbackHandler`.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{debug, error};
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::ContentType;
+use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+use serde_json::Value;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+use tonic::service::Interceptor;
+use tonic::transport::Server;
+use tracing::info_span;
+use uuid::Uuid;
+
+use crate::catalogue_manager::{all_entries, find_content_matcher, find_matcher};
+use crate::content::{convert_matching_rules, from_proto_body, ContentMismatch};
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::PactPluginRpc;
+use crate::proto::{
+  CatalogueEntries,
+  CatalogueEntry as ProtoCatalogueEntry,
+  CatalogueQuery,
+  CompareContentsRequest,
+  CompareContentsResponse,
+  ContentMismatches,
+  MatchValueRequest,
+  MatchValueResponse,
+  ProviderStateValuesRequest,
+  ProviderStateValuesResponse
+};
+use crate::proto::catalogue_entry::EntryType;
+use crate::proto::pact_plugin_host_server::{PactPluginHost, PactPluginHostServer};
+use crate::telemetry::set_parent_from_headers;
+use crate::utils::{proto_struct_to_json, proto_value_to_json, to_proto_struct};
+
+lazy_static! {
+  static ref CALLBACK_HANDLER: RwLock<Option<Arc<dyn CallbackHandler>>> = RwLock::new(None);
+  static ref CALLBACK_SERVICE: Mutex<Option<CallbackServiceDetails>> = Mutex::new(None);
+}
+
+/// Metadata entry plugins must send the callback token in
+pub const CALLBACK_TOKEN_METADATA_KEY: &str = "pact-callback-token";
+
+/// Details of the running callback service, passed to plugins in the `InitPluginRequest`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallbackServiceDetails {
+  /// Address the service is bound to
+  pub address: SocketAddr,
+  /// Token for this session that plugins must send with each call to the service
+  pub token: String
+}
+
+/// Handler provided by the Pact implementation to service the callbacks from plugins that need
+/// the core framework.
+pub trait CallbackHandler: Send + Sync {
+  /// Apply a core matching rule to the actual value, returning the descriptions of any mismatches
+  fn match_value(
+    &self,
+    rule: &MatchingRule,
+    expected: &Value,
+    actual: &Value,
+    path: &str
+  ) -> anyhow::Result<Vec<String>>;
+
+  /// Compare contents using a core content matcher (i.e. JSON or XML), returning any mismatches
+  /// keyed by path
+  fn match_contents(
+    &self,
+    expected: &OptionalBody,
+    actual: &OptionalBody,
+    rules: Option<&MatchingRuleCategory>,
+    allow_unexpected_keys: bool
+  ) -> anyhow::Result<HashMap<String, Vec<ContentMismatch>>>;
+
+  /// Values from the provider states for the interaction being verified
+  fn provider_state_values(&self, interaction_key: &str) -> HashMap<String, Value>;
+}
+
+/// Register the handler that will service the callbacks that need the core framework. This
+/// replaces any previously registered handler.
+pub fn set_callback_handler(handler: Arc<dyn CallbackHandler>) {
+  let mut guard = CALLBACK_HANDLER.write().unwrap();
+  *guard = Some(handler);
+}
+
+fn callback_handler() -> Option<Arc<dyn CallbackHandler>> {
+  CALLBACK_HANDLER.read().unwrap().clone()
+}
+
+/// Details of the callback service, starting it if it is not already running. Returns `None` if
+/// the host has not registered a callback handler, as the service is only provided to hosts that
+/// opt in to it.
+pub fn callback_service() -> anyhow::Result<Option<CallbackServiceDetails>> {
+  if callback_handler().is_none() {
+    return Ok(None);
+  }
+
+  let mut guard = CALLBACK_SERVICE.lock().unwrap();
+  if let Some(details) = guard.as_ref() {
+    return Ok(Some(details.clone()));
+  }
+
+  let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+  listener.set_nonblocking(true)?;
+  let address = listener.local_addr()?;
+  let token = Uuid::new_v4().to_string();
+  let interceptor = CallbackTokenInterceptor { token: token.clone() };
+  debug!("Starting plugin callback service on {}", address);
+  thread::Builder::new()
+    .name("pact-plugin-callbacks".to_string())
+    .spawn(move || {
+      let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+          error!("Failed to start the plugin callback service - {}", err);
+          return;
+        }
+      };
+      runtime.block_on(async move {
+        let result = match tokio::net::TcpListener::from_std(listener) {
+          Ok(listener) => Server::builder()
+            .trace_fn(|request| {
+              let span = info_span!("plugin_callback", path = %request.uri().path());
+              set_parent_from_headers(&span, request.headers());
+              span
+            })
+            .add_service(PactPluginHostServer::with_interceptor(PactPluginHostService, interceptor))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .map_err(|err| anyhow!(err)),
+          Err(err) => Err(anyhow!(err))
+        };
+        if let Err(err) = result {
+          error!("Plugin callback service failed - {}", err);
+        }
+      });
+    })?;
+
+  let details = CallbackServiceDetails { address, token };
+  *guard = Some(details.clone());
+  Ok(Some(details))
+}
+
+/// Rejects any calls to the callback service that do not have the token for the session
+#[derive(Clone, Debug)]
+struct CallbackTokenInterceptor {
+  token: String
+}
+
+impl Interceptor for CallbackTokenInterceptor {
+  fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+    match request.metadata().get(CALLBACK_TOKEN_METADATA_KEY) {
+      Some(value) if value == self.token.as_str() => Ok(request),
+      Some(_) => Err(Status::unauthenticated("Callback token is not valid")),
+      None => Err(Status::unauthenticated("Callback token is required"))
+    }
+  }
+}
+
+/// gRPC service that services the callbacks from plugins
+#[derive(Debug, Default)]
+struct PactPluginHostService;
+
+#[tonic::async_trait]
+impl PactPluginHost for PactPluginHostService {
+  async fn get_catalogue_entries(
+    &self,
+    request: Request<CatalogueQuery>
+  ) -> Result<Response<CatalogueEntries>, Status> {
+    let query = request.get_ref();
+    debug!("Plugin callback: get_catalogue_entries({:?})", query);
+    let entries = all_entries().iter()
+      .filter(|entry| query.r#type.is_empty() || entry.entry_type.to_string() == query.r#type)
+      .map(|entry| ProtoCatalogueEntry {
+        r#type: EntryType::from(entry.entry_type) as i32,
+        key: entry.catalogue_key(),
+        values: entry.values.clone()
+      })
+      .collect();
+    Ok(Response::new(CatalogueEntries { entries }))
+  }
+
+  async fn compare_contents(
+    &self,
+    request: Request<CompareContentsRequest>
+  ) -> Result<Response<CompareContentsResponse>, Status> {
+    let request = request.into_inner();
+    let content_type = request.expected.as_ref()
+      .map(|body| body.content_type.clone())
+      .unwrap_or_default();
+    debug!("Plugin callback: compare_contents for content type '{}'", content_type);
+
+    let matcher = ContentType::parse(content_type.as_str()).ok()
+      .and_then(|content_type| find_content_matcher(&content_type));
+    let response = match matcher {
+      Some(matcher) if !matcher.is_core() => {
+        let plugin = matcher.plugin().and_then(|plugin| lookup_plugin(&plugin.as_dependency()));
+        match plugin {
+          Some(plugin) => plugin.compare_contents(request).await
+            .unwrap_or_else(|err| compare_contents_error(format!("Call to plugin failed - {}", err))),
+          None => compare_contents_error(format!("Plugin for {:?} was not found in the plugin register",
+            matcher.catalogue_entry))
+        }
+      }
+      _ => match callback_handler() {
+        Some(handler) => {
+          let mut errors = vec![];
+          let rules = convert_matching_rules(&request.rules, &mut errors);
+          if errors.is_empty() {
+            match handler.match_contents(&from_proto_body(request.expected.as_ref()),
+              &from_proto_body(request.actual.as_ref()), rules.as_ref(), request.allow_unexpected_keys) {
+              Ok(mismatches) => CompareContentsResponse {
+                results: mismatches.iter()
+                  .map(|(path, mismatches)| (path.clone(), ContentMismatches {
+                    mismatches: mismatches.iter().map(|mismatch| mismatch.into()).collect()
+                  }))
+                  .collect(),
+                .. CompareContentsResponse::default()
+              },
+              Err(err) => compare_contents_error(err.to_string())
+            }
+          } else {
+            compare_contents_error(errors.join(", "))
+          }
+        }
+        None => compare_contents_error(format!("No content matcher is available for content type '{}'",
+          content_type))
+      }
+    };
+    Ok(Response::new(response))
+  }
+
+  async fn match_value(
+    &self,
+    request: Request<MatchValueRequest>
+  ) -> Result<Response<MatchValueResponse>, Status> {
+    let request = request.into_inner();
+    debug!("Plugin callback: match_value for matching rule '{}'", request.name);
+
+    let response = match find_matcher(request.name.as_str()) {
+      Some(matcher) if !matcher.is_core() => {
+        let plugin = matcher.plugin().and_then(|plugin| lookup_plugin(&plugin.as_dependency()));
+        match plugin {
+          Some(plugin) => plugin.match_value(request).await
+            .unwrap_or_else(|err| match_value_error(format!("Call to plugin failed - {}", err))),
+          None => match_value_error(format!("Plugin for {:?} was not found in the plugin register",
+            matcher.catalogue_entry))
+        }
+      }
+      Some(matcher) => match callback_handler() {
+        Some(handler) => {
+          // Core matchers can be looked up by catalogue key (i.e. v2-regex), so use the rule type
+          let rule_type = matcher.catalogue_entry.values.get("matching-rule")
+            .cloned()
+            .unwrap_or_else(|| request.name.clone());
+          let values = request.values.as_ref().map(proto_struct_to_json).unwrap_or_default();
+          let expected = request.expected.as_ref().map(proto_value_to_json).unwrap_or_default();
+          let actual = request.actual.as_ref().map(proto_value_to_json).unwrap_or_default();
+          match MatchingRule::create(rule_type.as_str(), &values)
+            .and_then(|rule| handler.match_value(&rule, &expected, &actual, request.path.as_str())) {
+            Ok(mismatches) => MatchValueResponse { error: String::default(), mismatches },
+            Err(err) => match_value_error(err.to_string())
+          }
+        }
+        None => match_value_error("The Pact implementation does not support core matching rules".to_string())
+      },
+      None => match_value_error(format!("'{}' is not a known matching rule", request.name))
+    };
+    Ok(Response::new(response))
+  }
+
+  async fn get_provider_state_values(
+    &self,
+    request: Request<ProviderStateValuesRequest>
+  ) -> Result<Response<ProviderStateValuesResponse>, Status> {
+    let request = request.get_ref();
+    debug!("Plugin callback: get_provider_state_values for interaction '{}'", request.interaction_key);
+    let values = callback_handler()
+      .map(|handler| handler.provider_state_values(request.interaction_key.as_str()))
+      .unwrap_or_default();
+    Ok(Response::new(ProviderStateValuesResponse {
+      values: Some(to_proto_struct(values))
+    }))
+  }
+}
+
+fn compare_contents_error(error: String) -> CompareContentsResponse {
+  error!("Plugin callback failed - {}", error);
+  CompareContentsResponse {
+    error,
+    .. CompareContentsResponse::default()
+  }
+}
+
+fn match_value_error(error: String) -> MatchValueResponse {
+  error!("Plugin callback failed - {}", error);
+  MatchValueResponse {
+    error,
+    mismatches: vec![]
+  }
+}
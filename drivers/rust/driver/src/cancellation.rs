@@ -0,0 +1,38 @@
+//! Support for cancelling calls to plugins. Hosts can create a `CancellationToken` for a Pact (or
+//! test) and pass it (or a child token) to the plugin calls. Cancelling the token will abort all the
+//! outstanding calls that were given it.
+//!
+//! Aborting a call drops the gRPC request to the plugin, which resets the HTTP/2 stream. The
+//! plugin will then see the request as cancelled, and stop processing it.
+
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Error returned when a call to a plugin is cancelled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Call to plugin was cancelled")
+  }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Runs the future until it completes or the cancellation token is cancelled. If the token is
+/// cancelled first, the future is dropped and `Cancelled` is returned. If no token is given, the
+/// future will always be run to completion.
+pub async fn with_cancellation<F, T>(token: Option<&CancellationToken>, future: F) -> Result<T, Cancelled>
+  where F: Future<Output = T> {
+  match token {
+    Some(token) => tokio::select! {
+      biased;
+      _ = token.cancelled() => Err(Cancelled),
+      result = future => Ok(result)
+    },
+    None => Ok(future.await)
+  }
+}
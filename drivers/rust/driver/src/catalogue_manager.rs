@@ -1,5 +1,6 @@
 //! Manages the catalogue of features provided by plugins
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 use std::sync::Mutex;
@@ -7,19 +8,31 @@ use std::sync::Mutex;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use log::{debug, error, trace};
-use maplit::hashset;
+use maplit::{hashmap, hashset};
 use regex::Regex;
+use semver::Version;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 
 use pact_models::content_types::ContentType;
+use pact_models::generators::Generator;
 
 use crate::content::{ContentMatcher, ContentGenerator};
-use crate::plugin_models::PactPluginManifest;
+use crate::generators::PluginGenerator;
+use crate::matchers::PluginMatcher;
+use crate::plugin_models::{
+  CAPABILITY_COMPARE_CONTENTS_BATCH,
+  CAPABILITY_START_MOCK_SERVER,
+  PactPluginManifest,
+  PluginDependency,
+  PluginDependencyType
+};
 use crate::proto::{CatalogueEntry as ProtoCatalogueEntry};
 use crate::proto::catalogue_entry::EntryType;
 
 lazy_static! {
-  static ref CATALOGUE_REGISTER: Mutex<HashMap<String, CatalogueEntry>> = Mutex::new(HashMap::new());
+  static ref CATALOGUE_REGISTER: Mutex<HashMap<String, CatalogueEntry>> = Mutex::new(core_catalogue_register());
+  static ref CATALOGUE_EVENTS: broadcast::Sender<CatalogueEvent> = broadcast::channel(256).0;
 }
 
 /// Type of catalogue entry
@@ -34,8 +47,10 @@ pub enum CatalogueEntryType {
   MOCK_SERVER,
   /// Matching rule
   MATCHER,
-  /// Generator
-  INTERACTION
+  /// Type of interaction
+  INTERACTION,
+  /// Generator for individual values
+  GENERATOR
 }
 
 impl Display for CatalogueEntryType {
@@ -46,6 +61,7 @@ impl Display for CatalogueEntryType {
       CatalogueEntryType::MOCK_SERVER => write!(f, "mock-server"),
       CatalogueEntryType::MATCHER => write!(f, "matcher"),
       CatalogueEntryType::INTERACTION => write!(f, "interaction"),
+      CatalogueEntryType::GENERATOR => write!(f, "generator"),
     }
   }
 }
@@ -56,6 +72,7 @@ impl From<&str> for CatalogueEntryType {
       "content-matcher" => CatalogueEntryType::CONTENT_MATCHER,
       "content-generator" => CatalogueEntryType::CONTENT_GENERATOR,
       "interaction" => CatalogueEntryType::INTERACTION,
+      "generator" => CatalogueEntryType::GENERATOR,
       "matcher" => CatalogueEntryType::MATCHER,
       "mock-server" => CatalogueEntryType::MOCK_SERVER,
       _ => {
@@ -80,7 +97,21 @@ impl From<EntryType> for CatalogueEntryType {
       EntryType::ContentGenerator => CatalogueEntryType::CONTENT_GENERATOR,
       EntryType::MockServer => CatalogueEntryType::MOCK_SERVER,
       EntryType::Matcher => CatalogueEntryType::MATCHER,
-      EntryType::Interaction => CatalogueEntryType::INTERACTION
+      EntryType::Interaction => CatalogueEntryType::INTERACTION,
+      EntryType::Generator => CatalogueEntryType::GENERATOR
+    }
+  }
+}
+
+impl From<CatalogueEntryType> for EntryType {
+  fn from(t: CatalogueEntryType) -> Self {
+    match t {
+      CatalogueEntryType::CONTENT_MATCHER => EntryType::ContentMatcher,
+      CatalogueEntryType::CONTENT_GENERATOR => EntryType::ContentGenerator,
+      CatalogueEntryType::MOCK_SERVER => EntryType::MockServer,
+      CatalogueEntryType::MATCHER => EntryType::Matcher,
+      CatalogueEntryType::INTERACTION => EntryType::Interaction,
+      CatalogueEntryType::GENERATOR => EntryType::Generator
     }
   }
 }
@@ -111,7 +142,207 @@ pub struct CatalogueEntry {
   pub values: HashMap<String, String>
 }
 
-/// Register the entries in the global catalogue
+impl CatalogueEntry {
+  /// Full key of the entry in the catalogue (i.e. `core/content-matcher/json` or
+  /// `plugin/csv/content-matcher/csv`). Plugin entries already store the full key.
+  pub fn catalogue_key(&self) -> String {
+    match self.provider_type {
+      CatalogueEntryProviderType::CORE => format!("core/{}/{}", self.entry_type, self.key),
+      CatalogueEntryProviderType::PLUGIN => self.key.clone()
+    }
+  }
+
+  /// Version of the plugin that provided this entry. Core entries have no version.
+  pub fn plugin_version(&self) -> Option<String> {
+    self.plugin.as_ref().map(|plugin| plugin.version.clone())
+  }
+
+  /// If the entry itself declares the optional plugin capability (see `PactPluginRpc::supports_for_entry`).
+  /// Entries can list capabilities in the `capabilities` value (separated with `;`). Mock server
+  /// entries also declare `StartMockServer`, and content matchers with the `batch-compare` value
+  /// set declare `CompareContentsBatch`.
+  pub fn declares_capability(&self, capability: &str) -> bool {
+    let listed = self.values.get("capabilities")
+      .map(|capabilities| capabilities.split(';').any(|c| c.trim() == capability))
+      .unwrap_or(false);
+    listed || match capability {
+      CAPABILITY_START_MOCK_SERVER => self.entry_type == CatalogueEntryType::MOCK_SERVER,
+      CAPABILITY_COMPARE_CONTENTS_BATCH => self.entry_type == CatalogueEntryType::CONTENT_MATCHER &&
+        self.values.get("batch-compare").map(|v| v == "true").unwrap_or(false),
+      _ => false
+    }
+  }
+
+  /// If this entry was provided by the plugin given by the dependency. If the dependency does
+  /// not specify a version, any version of the plugin will match.
+  pub fn provided_by(&self, plugin: &PluginDependency) -> bool {
+    match &self.plugin {
+      Some(manifest) => manifest.name == plugin.name && plugin.version.as_ref()
+        .map(|version| version == &manifest.version)
+        .unwrap_or(true),
+      None => false
+    }
+  }
+
+  // Key used to store the entry in the register. Plugin entries are scoped to the version of the
+  // plugin so that different versions of the same plugin can be loaded at the same time.
+  fn register_key(&self) -> String {
+    match self.plugin_version() {
+      Some(version) => format!("{}/{}", self.catalogue_key(), version),
+      None => self.catalogue_key()
+    }
+  }
+}
+
+/// Compares two plugin versions as semantic versions (so `0.0.10` is after `0.0.9`). Versions that
+/// are not valid semantic versions are compared as strings.
+pub(crate) fn compare_versions(v1: &str, v2: &str) -> Ordering {
+  match (Version::parse(v1), Version::parse(v2)) {
+    (Ok(version1), Ok(version2)) => version1.cmp(&version2),
+    _ => v1.cmp(v2)
+  }
+}
+
+// Orders entries by the version of the plugin that provided them. Core entries have no version,
+// so are before all plugin entries.
+fn compare_entry_versions(e1: &CatalogueEntry, e2: &CatalogueEntry) -> Ordering {
+  match (e1.plugin_version(), e2.plugin_version()) {
+    (Some(v1), Some(v2)) => compare_versions(&v1, &v2),
+    (v1, v2) => v1.cmp(&v2)
+  }
+}
+
+/// Change to the global catalogue
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatalogueEvent {
+  /// Entry was added to the catalogue
+  Added(CatalogueEntry),
+  /// Entry was removed from the catalogue
+  Removed(CatalogueEntry)
+}
+
+/// Subscribe to changes to the global catalogue. Events are published as entries are registered
+/// or removed. Receivers that fall too far behind will miss events (see `broadcast::Receiver`).
+pub fn subscribe_to_catalogue_changes() -> broadcast::Receiver<CatalogueEvent> {
+  CATALOGUE_EVENTS.subscribe()
+}
+
+fn publish_catalogue_event(event: CatalogueEvent) {
+  trace!("publish_catalogue_event({:?})", event);
+  // An error here just means there are no subscribers
+  let _ = CATALOGUE_EVENTS.send(event);
+}
+
+fn core_catalogue_register() -> HashMap<String, CatalogueEntry> {
+  let entries = core_catalogue_entries();
+  debug!("Registering core catalogue entries:\n{}", entries.iter()
+    .map(|entry| format!("core/{}/{}", entry.entry_type, entry.key)).sorted().join("\n"));
+  entries.into_iter()
+    .map(|entry| (entry.register_key(), entry))
+    .collect()
+}
+
+/// Returns the standard catalogue entries provided by the core Pact framework. These are
+/// registered with the global catalogue the first time it is used.
+pub fn core_catalogue_entries() -> Vec<CatalogueEntry> {
+  let mut entries = vec![];
+
+  for interaction in ["http", "https", "message", "synchronous-message"] {
+    entries.push(core_entry(CatalogueEntryType::INTERACTION, interaction, hashmap!{}));
+  }
+
+  for (key, rule) in core_matching_rules() {
+    entries.push(core_entry(CatalogueEntryType::MATCHER, key, hashmap!{
+      "matching-rule".to_string() => rule.to_string()
+    }));
+  }
+
+  for (key, content_types) in [
+    ("xml", "application/.*xml;text/xml"),
+    ("json", "application/.*json;application/json-rpc;application/jsonrequest"),
+    ("text", "text/plain"),
+    ("multipart-form-data", "multipart/form-data;multipart/mixed"),
+    ("form-urlencoded", "application/x-www-form-urlencoded")
+  ] {
+    entries.push(core_entry(CatalogueEntryType::CONTENT_MATCHER, key, hashmap!{
+      "content-types".to_string() => content_types.to_string()
+    }));
+  }
+
+  entries.push(core_entry(CatalogueEntryType::CONTENT_GENERATOR, "json", hashmap!{
+    "content-types".to_string() => "application/.*json;application/json-rpc;application/jsonrequest".to_string()
+  }));
+
+  for generator in core_generators() {
+    entries.push(core_entry(CatalogueEntryType::GENERATOR, generator.name().as_str(), hashmap!{}));
+  }
+
+  entries
+}
+
+fn core_entry(entry_type: CatalogueEntryType, key: &str, values: HashMap<String, String>) -> CatalogueEntry {
+  CatalogueEntry {
+    entry_type,
+    provider_type: CatalogueEntryProviderType::CORE,
+    plugin: None,
+    key: key.to_string(),
+    values
+  }
+}
+
+/// Core matching rules (see docs/plugin-driver-design.md), keyed by the core catalogue entry key,
+/// with the matching rule type
+fn core_matching_rules() -> Vec<(&'static str, &'static str)> {
+  vec![
+    ("v1-equality", "equality"),
+    ("v2-regex", "regex"),
+    ("v2-type", "type"),
+    ("v2-min-type", "min-type"),
+    ("v2-max-type", "max-type"),
+    ("v2-minmax-type", "min-max-type"),
+    ("v3-number-type", "number"),
+    ("v3-integer-type", "integer"),
+    ("v3-decimal-type", "decimal"),
+    ("v3-date", "date"),
+    ("v3-time", "time"),
+    ("v3-datetime", "datetime"),
+    ("v3-includes", "include"),
+    ("v3-null", "null"),
+    ("v3-content-type", "content-type"),
+    ("v3-values", "values"),
+    ("v3-boolean", "boolean"),
+    ("v4-equals-ignore-order", "ignore-order"),
+    ("v4-min-equals-ignore-order", "ignore-order"),
+    ("v4-max-equals-ignore-order", "ignore-order"),
+    ("v4-minmax-equals-ignore-order", "ignore-order"),
+    ("v4-array-contains", "array-contains"),
+    ("v4-status-code", "status-code"),
+    ("v4-not-empty", "not-empty"),
+    ("v4-semver", "semver")
+  ]
+}
+
+/// Generators supported by the Pact models
+fn core_generators() -> Vec<Generator> {
+  vec![
+    Generator::RandomInt(0, 0),
+    Generator::Uuid(None),
+    Generator::RandomDecimal(0),
+    Generator::RandomHexadecimal(0),
+    Generator::RandomString(0),
+    Generator::Regex(String::default()),
+    Generator::Date(None),
+    Generator::Time(None),
+    Generator::DateTime(None),
+    Generator::RandomBoolean,
+    Generator::ProviderStateGenerator(String::default(), None),
+    Generator::MockServerURL(String::default(), String::default()),
+    Generator::ArrayContains(vec![])
+  ]
+}
+
+/// Register the entries in the global catalogue. Entries are scoped to the version of the plugin,
+/// so registering entries for a different version of the plugin will not replace any existing ones.
 pub fn register_plugin_entries(plugin: &PactPluginManifest, catalogue_list: &Vec<ProtoCatalogueEntry>) {
   trace!("register_plugin_entries({:?}, {:?})", plugin, catalogue_list);
 
@@ -120,13 +351,17 @@ pub fn register_plugin_entries(plugin: &PactPluginManifest, catalogue_list: &Vec
   for entry in catalogue_list {
     let entry_type = CatalogueEntryType::from(entry.r#type());
     let key = format!("plugin/{}/{}/{}", plugin.name, entry_type, entry.key);
-    guard.insert(key.clone(), CatalogueEntry {
+    let entry = CatalogueEntry {
       entry_type,
       provider_type: CatalogueEntryProviderType::PLUGIN,
       plugin: Some(plugin.clone()),
-      key: key.clone(),
+      key,
       values: entry.values.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
-    });
+    };
+    if let Some(previous) = guard.insert(entry.register_key(), entry.clone()) {
+      publish_catalogue_event(CatalogueEvent::Removed(previous));
+    }
+    publish_catalogue_event(CatalogueEvent::Added(entry));
   }
 
   debug!("Updated catalogue entries:\n{}", guard.keys().sorted().join("\n"))
@@ -140,10 +375,11 @@ pub fn register_core_entries(entries: &Vec<CatalogueEntry>) {
 
   let mut updated_keys = hashset!();
   for entry in entries {
-    let key = format!("core/{}/{}", entry.entry_type, entry.key);
+    let key = entry.register_key();
     if !inner.contains_key(&key) {
       inner.insert(key.clone(), entry.clone());
       updated_keys.insert(key.clone());
+      publish_catalogue_event(CatalogueEvent::Added(entry.clone()));
     }
   }
 
@@ -152,33 +388,83 @@ pub fn register_core_entries(entries: &Vec<CatalogueEntry>) {
   }
 }
 
-/// Remove entries for a plugin
-pub fn remove_plugin_entries(name: &String) {
-  trace!("remove_plugin_entries({})", name);
+/// Remove the entries for a particular version of a plugin. Entries for other versions of the
+/// plugin are not affected.
+pub fn remove_plugin_entries(name: &str, version: &str) {
+  trace!("remove_plugin_entries({}, {})", name, version);
 
-  let prefix = format!("plugin/{}/", name);
-  let keys: Vec<String> = {
-    let guard = CATALOGUE_REGISTER.lock().unwrap();
-    guard.keys()
-      .filter(|key| key.starts_with(&prefix))
-      .cloned()
-      .collect()
+  let plugin = PluginDependency {
+    name: name.to_string(),
+    version: Some(version.to_string()),
+    dependency_type: PluginDependencyType::Plugin
   };
-
   let mut guard = CATALOGUE_REGISTER.lock().unwrap();
+  let keys: Vec<String> = guard.iter()
+    .filter(|(_, entry)| entry.provided_by(&plugin))
+    .map(|(key, _)| key.clone())
+    .collect();
+
   for key in keys {
-    guard.remove(&key);
+    if let Some(entry) = guard.remove(&key) {
+      publish_catalogue_event(CatalogueEvent::Removed(entry));
+    }
   }
 
-  debug!("Removed all catalogue entries for plugin {}", name);
+  debug!("Removed all catalogue entries for plugin {}/{}", name, version);
 }
 
-/// Find a content matcher in the global catalogue for the provided content type
+/// Returns all the entries in the global catalogue
+pub fn all_entries() -> Vec<CatalogueEntry> {
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values().cloned().collect()
+}
+
+/// Lookup an entry in the global catalogue by key. If more than one version of a plugin has
+/// provided the entry, the entry from the latest version is returned.
+pub fn lookup_entry(key: &str) -> Option<CatalogueEntry> {
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values()
+    .filter(|entry| entry.catalogue_key() == key)
+    .max_by(|e1, e2| compare_entry_versions(e1, e2))
+    .cloned()
+}
+
+/// Lookup an entry in the global catalogue by key, provided by the given version of the plugin
+pub fn lookup_entry_for_version(key: &str, plugin_version: &str) -> Option<CatalogueEntry> {
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values()
+    .find(|entry| entry.catalogue_key() == key &&
+      entry.plugin_version().as_deref() == Some(plugin_version))
+    .cloned()
+}
+
+/// Find a content matcher in the global catalogue for the provided content type. Plugin provided
+/// matchers take precedence over the core ones, and the latest version of a plugin is used if
+/// more than one is loaded.
 pub fn find_content_matcher(content_type: &ContentType) -> Option<ContentMatcher> {
+  find_content_matcher_for_plugins(content_type, &[])
+}
+
+/// Find a content matcher in the global catalogue for the provided content type, only considering
+/// entries from the given plugins (i.e. the plugins and versions that a Pact file requires).
+/// Core matchers are always considered. If the list of plugins is empty, all plugins are considered.
+pub fn find_content_matcher_for_plugins(
+  content_type: &ContentType,
+  plugins: &[PluginDependency]
+) -> Option<ContentMatcher> {
   debug!("Looking for a content matcher for {}", content_type);
+  find_content_type_entry(CatalogueEntryType::CONTENT_MATCHER, content_type, plugins)
+    .map(|entry| ContentMatcher { catalogue_entry: entry })
+}
+
+fn find_content_type_entry(
+  entry_type: CatalogueEntryType,
+  content_type: &ContentType,
+  plugins: &[PluginDependency]
+) -> Option<CatalogueEntry> {
   let guard = CATALOGUE_REGISTER.lock().unwrap();
-  guard.values().find(|entry| {
-    if entry.entry_type == CatalogueEntryType::CONTENT_MATCHER {
+  guard.values().filter(|entry| {
+    if entry.entry_type == entry_type {
       if let Some(content_types) = entry.values.get("content-types") {
         content_types.split(";").any(|ct| matches_pattern(ct.trim(), content_type))
       } else {
@@ -187,7 +473,13 @@ pub fn find_content_matcher(content_type: &ContentType) -> Option<ContentMatcher
     } else {
       false
     }
-  }).map(|entry| ContentMatcher { catalogue_entry: entry.clone() })
+  }).filter(|entry| {
+    plugins.is_empty() || entry.plugin.is_none() || plugins.iter().any(|plugin| entry.provided_by(plugin))
+  }).max_by(|e1, e2| {
+    let is_plugin = |entry: &CatalogueEntry| entry.provider_type == CatalogueEntryProviderType::PLUGIN;
+    is_plugin(e1).cmp(&is_plugin(e2)).then_with(|| compare_entry_versions(e1, e2))
+  })
+    .cloned()
 }
 
 fn matches_pattern(pattern: &str, content_type: &ContentType) -> bool {
@@ -201,19 +493,97 @@ fn matches_pattern(pattern: &str, content_type: &ContentType) -> bool {
   }
 }
 
-/// Find a content genetrator in the global catalogue for the provided content type
+/// Find a content genetrator in the global catalogue for the provided content type. Plugin
+/// provided generators take precedence over the core ones, and the latest version of a plugin is
+/// used if more than one is loaded.
 pub fn find_content_generator(content_type: &ContentType) -> Option<ContentGenerator> {
+  find_content_generator_for_plugins(content_type, &[])
+}
+
+/// Find a content genetrator in the global catalogue for the provided content type, only
+/// considering entries from the given plugins. If the list of plugins is empty, all plugins are
+/// considered.
+pub fn find_content_generator_for_plugins(
+  content_type: &ContentType,
+  plugins: &[PluginDependency]
+) -> Option<ContentGenerator> {
   debug!("Looking for a content generator for {}", content_type);
+  find_content_type_entry(CatalogueEntryType::CONTENT_GENERATOR, content_type, plugins)
+    .map(|entry| ContentGenerator { catalogue_entry: entry })
+}
+
+/// Find a matcher for a matching rule in the global catalogue. For plugin provided matchers, the
+/// name is the key the plugin registered the matcher with (i.e. `csv-length`). Core matchers can be
+/// found using either the catalogue key (i.e. `v2-regex`) or the matching rule type (i.e. `regex`),
+/// and take precedence over any plugin matchers with the same name.
+pub fn find_matcher(name: &str) -> Option<PluginMatcher> {
+  debug!("Looking for a matcher for matching rule '{}'", name);
   let guard = CATALOGUE_REGISTER.lock().unwrap();
-  guard.values().find(|entry| {
-    if entry.entry_type == CatalogueEntryType::CONTENT_GENERATOR {
-      if let Some(content_types) = entry.values.get("content-types") {
-        content_types.split(";").any(|ct| matches_pattern(ct.trim(), content_type))
-      } else {
-        false
+  guard.values().filter(|entry| {
+    if entry.entry_type == CatalogueEntryType::MATCHER {
+      match &entry.plugin {
+        Some(plugin) => entry.key == format!("plugin/{}/matcher/{}", plugin.name, name),
+        None => entry.key == name || entry.values.get("matching-rule").map(|rule| rule == name).unwrap_or(false)
       }
     } else {
       false
     }
-  }).map(|entry| ContentGenerator { catalogue_entry: entry.clone() })
+  }).max_by(|e1, e2| {
+    let is_core = |entry: &CatalogueEntry| entry.provider_type == CatalogueEntryProviderType::CORE;
+    is_core(e1).cmp(&is_core(e2)).then_with(|| compare_entry_versions(e1, e2))
+  })
+    .map(|entry| PluginMatcher { catalogue_entry: entry.clone() })
+}
+
+/// Find a generator for individual values in the global catalogue. For plugin provided generators,
+/// the name is the key the plugin registered the generator with (i.e. `fake-iban`). Core generators
+/// (i.e. `RandomInt`) take precedence over any plugin generators with the same name.
+pub fn find_generator(name: &str) -> Option<PluginGenerator> {
+  debug!("Looking for a generator '{}'", name);
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values().filter(|entry| {
+    if entry.entry_type == CatalogueEntryType::GENERATOR {
+      match &entry.plugin {
+        Some(plugin) => entry.key == format!("plugin/{}/generator/{}", plugin.name, name),
+        None => entry.key == name
+      }
+    } else {
+      false
+    }
+  }).max_by(|e1, e2| {
+    let is_core = |entry: &CatalogueEntry| entry.provider_type == CatalogueEntryProviderType::CORE;
+    is_core(e1).cmp(&is_core(e2)).then_with(|| compare_entry_versions(e1, e2))
+  })
+    .map(|entry| PluginGenerator { catalogue_entry: entry.clone() })
+}
+
+/// Find a mock server for a transport (i.e. `grpc` or `tcp`) in the global catalogue. Mock servers
+/// are provided by plugins, using the transport as the key of the catalogue entry. The latest
+/// version of a plugin is used if more than one is loaded.
+pub fn find_mock_server(transport: &str) -> Option<CatalogueEntry> {
+  debug!("Looking for a mock server for transport '{}'", transport);
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values().filter(|entry| {
+    entry.entry_type == CatalogueEntryType::MOCK_SERVER && match &entry.plugin {
+      Some(plugin) => entry.key == format!("plugin/{}/mock-server/{}", plugin.name, transport),
+      None => entry.key == transport
+    }
+  }).max_by(|e1, e2| compare_entry_versions(e1, e2))
+    .cloned()
+}
+
+/// Find a plugin provided interaction type (i.e. `produce` or `plugin/kafka/interaction/produce`)
+/// in the global catalogue. The plugin that owns the interaction type is the `plugin` of the
+/// returned entry. The latest version of a plugin is used if more than one is loaded.
+pub fn find_interaction_type(interaction_type: &str) -> Option<CatalogueEntry> {
+  debug!("Looking for a plugin that provides interaction type '{}'", interaction_type);
+  let guard = CATALOGUE_REGISTER.lock().unwrap();
+  guard.values().filter(|entry| {
+    entry.entry_type == CatalogueEntryType::INTERACTION && match &entry.plugin {
+      Some(plugin) => entry.key == interaction_type ||
+        entry.key == format!("plugin/{}/interaction/{}", plugin.name, interaction_type),
+      None => false
+    }
+  }).max_by(|e1, e2| compare_entry_versions(e1, e2))
+    .cloned()
 }
@@ -23,6 +23,7 @@ pub struct RunningPluginInfo {
 #[derive(Debug, Clone)]
 pub struct ChildPluginProcess {
   child_pid: usize,
+  #[allow(dead_code)]
   manifest: PactPluginManifest,
   plugin_info: RunningPluginInfo
 }
@@ -1,30 +1,58 @@
 //! Support for matching and generating content based on content types
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
 use std::str::from_utf8;
 
 use anyhow::anyhow;
 use bytes::Bytes;
+use itertools::Itertools;
 use log::{debug, error};
 use maplit::hashmap;
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentTypeHint;
 use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleList};
 use pact_models::path_exp::DocPath;
+use pact_models::generators::GeneratorTestMode;
 use pact_models::prelude::{ContentType, Generator, GeneratorCategory, Generators, RuleLogic};
 use serde_json::Value;
+use tracing::instrument;
 
+use crate::cancellation::{Cancelled, CancellationToken, with_cancellation};
 use crate::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType};
+use crate::diff::generate_diff;
+use crate::markup::MarkupType;
 use crate::plugin_manager::lookup_plugin;
-use crate::plugin_models::{PactPluginManifest, PluginInteractionConfig, PactPluginRpc};
+use crate::plugin_models::{
+  CAPABILITY_COMPARE_CONTENTS_BATCH,
+  CAPABILITY_COMPARE_STREAM,
+  PactPluginManifest,
+  PluginInteractionConfig,
+  PactPluginRpc
+};
 use crate::proto::{
   Body,
+  CompareContentsBatchRequest,
   CompareContentsRequest,
+  CompareContentsResponse,
+  CompareStreamRequest,
+  ContentMismatch as ProtoContentMismatch,
+  ContentMismatches,
   ConfigureInteractionRequest,
+  ConfigureInteractionResponse,
+  ExpectedStreamMessage,
   GenerateContentRequest,
-  PluginConfiguration as ProtoPluginConfiguration
+  Generator as ProtoGenerator,
+  MatchingRules as ProtoMatchingRules,
+  PluginConfiguration as ProtoPluginConfiguration,
+  StreamCount as ProtoStreamCount,
+  StreamMessage as ProtoStreamMessage,
+  StreamMismatch as ProtoStreamMismatch
 };
 use crate::proto::body;
-use crate::proto::interaction_response::MarkupType;
+use crate::proto::compare_contents_batch_request;
+use crate::proto::generate_content_request;
+use crate::proto::interaction_response::MessageDirection as ProtoMessageDirection;
 use crate::utils::{proto_struct_to_json, proto_struct_to_map, to_proto_struct};
 
 /// Matcher for contents based on content type
@@ -44,12 +72,10 @@ impl ContentMatcher {
 /// Mismatch result
 #[derive(Clone, Debug)]
 pub struct ContentMismatch {
-  /// Expected value in string format
-  // TODO: change to bytes
-  pub expected: String,
-  /// Actual value in string format
-  // TODO: change to bytes
-  pub actual: String,
+  /// Expected value
+  pub expected: Bytes,
+  /// Actual value
+  pub actual: Bytes,
   /// Mismatch description
   pub mismatch: String,
   /// Path to the mismatch
@@ -58,8 +84,127 @@ pub struct ContentMismatch {
   pub diff: Option<String>
 }
 
-/// Interaction contents setup by the plugin
+impl ContentMismatch {
+  /// Expected value rendered for display (see `display_bytes`)
+  pub fn expected_display(&self) -> String {
+    display_bytes(&self.expected)
+  }
+
+  /// Actual value rendered for display (see `display_bytes`)
+  pub fn actual_display(&self) -> String {
+    display_bytes(&self.actual)
+  }
+}
+
+impl From<&ProtoContentMismatch> for ContentMismatch {
+  fn from(mismatch: &ProtoContentMismatch) -> Self {
+    ContentMismatch {
+      expected: mismatch.expected.clone().map(Bytes::from).unwrap_or_default(),
+      actual: mismatch.actual.clone().map(Bytes::from).unwrap_or_default(),
+      mismatch: mismatch.mismatch.clone(),
+      path: mismatch.path.clone(),
+      diff: if mismatch.diff.is_empty() {
+        None
+      } else {
+        Some(mismatch.diff.clone())
+      }
+    }
+  }
+}
+
+impl From<&ContentMismatch> for ProtoContentMismatch {
+  fn from(mismatch: &ContentMismatch) -> Self {
+    ProtoContentMismatch {
+      expected: Some(mismatch.expected.to_vec()),
+      actual: Some(mismatch.actual.to_vec()),
+      mismatch: mismatch.mismatch.clone(),
+      path: mismatch.path.clone(),
+      diff: mismatch.diff.clone().unwrap_or_default()
+    }
+  }
+}
+
+/// Reasons that matching contents with a plugin can fail
 #[derive(Clone, Debug)]
+pub enum ContentMatchError {
+  /// The plugin reported that the content types do not match
+  TypeMismatch {
+    /// Expected content type
+    expected: String,
+    /// Actual content type
+    actual: String
+  },
+  /// The plugin returned an error
+  PluginError(String),
+  /// The plugin that provides the content matcher is not loaded
+  PluginNotFound {
+    /// Plugin name
+    name: String,
+    /// Plugin version
+    version: String
+  },
+  /// The call to the plugin failed
+  TransportError(String),
+  /// The call to the plugin was cancelled
+  Cancelled,
+  /// The content matcher is provided by the core framework, so there is no plugin to call
+  CoreMatcher(String),
+  /// The contents did not match, keyed by the category (body, message metadata or HTTP headers)
+  /// and then by path
+  Mismatches(HashMap<Category, HashMap<String, Vec<ContentMismatch>>>),
+  /// A stream of messages did not match the expected sequence of messages
+  StreamMismatches(Vec<StreamMismatch>)
+}
+
+impl ContentMatchError {
+  /// If this error is due to the contents not matching (as opposed to a failure calling the plugin)
+  pub fn is_mismatch(&self) -> bool {
+    matches!(self, ContentMatchError::TypeMismatch { .. } | ContentMatchError::Mismatches(_) |
+      ContentMatchError::StreamMismatches(_))
+  }
+}
+
+impl Display for ContentMatchError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      ContentMatchError::TypeMismatch { expected, actual } =>
+        write!(f, "Expected content type '{}' but got '{}'", expected, actual),
+      ContentMatchError::PluginError(err) => write!(f, "Plugin failed to compare the contents - {}", err),
+      ContentMatchError::PluginNotFound { name, version } =>
+        write!(f, "Plugin {}/{} was not found in the plugin register", name, version),
+      ContentMatchError::TransportError(err) => write!(f, "Call to plugin failed - {}", err),
+      ContentMatchError::Cancelled => write!(f, "{}", Cancelled),
+      ContentMatchError::CoreMatcher(key) =>
+        write!(f, "Content matcher '{}' is provided by the core framework, not a plugin", key),
+      ContentMatchError::Mismatches(mismatches) => {
+        let count = mismatches.values().flat_map(|m| m.values()).map(|m| m.len()).sum::<usize>();
+        write!(f, "Contents did not match ({} mismatches)", count)
+      }
+      ContentMatchError::StreamMismatches(mismatches) =>
+        write!(f, "Stream of messages did not match ({} mismatches)", mismatches.len())
+    }
+  }
+}
+
+impl std::error::Error for ContentMatchError {}
+
+impl From<Cancelled> for ContentMatchError {
+  fn from(_: Cancelled) -> Self {
+    ContentMatchError::Cancelled
+  }
+}
+
+/// Renders the bytes for display. Bytes that are valid UTF-8 are returned as a string, otherwise
+/// they are rendered as a hex string (i.e. `0x0a1bff`).
+pub fn display_bytes(bytes: &[u8]) -> String {
+  match from_utf8(bytes) {
+    Ok(s) => s.to_string(),
+    Err(_) => format!("0x{}", bytes.iter().map(|b| format!("{:02x}", b)).join(""))
+  }
+}
+
+/// Interaction contents setup by the plugin
+#[derive(Clone, Debug, Default)]
 pub struct InteractionContents {
   /// Description of what part this interaction belongs to (in the case of there being more than
   /// one, for instance, request/response messages)
@@ -84,26 +229,112 @@ pub struct InteractionContents {
   pub interaction_markup: String,
 
   /// The type of the markup (CommonMark or HTML)
-  pub interaction_markup_type: String
-}
-
-impl Default for InteractionContents {
-  fn default() -> Self {
-    InteractionContents {
-      part_name: Default::default(),
-      body: Default::default(),
-      rules: None,
-      generators: None,
-      metadata: None,
-      plugin_config: Default::default(),
-      interaction_markup: Default::default(),
-      interaction_markup_type: Default::default()
+  pub interaction_markup_type: MarkupType,
+
+  /// Interaction level fields set by the plugin (only for plugin provided interaction types)
+  pub interaction_fields: HashMap<String, Value>,
+
+  /// For streams of messages, the direction of this part
+  pub direction: Option<MessageDirection>,
+
+  /// For streams of messages, the position of this part in the stream
+  pub sequence: u32,
+
+  /// For streams of messages, the number of times this part can occur. If not set, the part is
+  /// expected exactly once.
+  pub count: Option<StreamCount>
+}
+
+/// Direction of a message that is part of a stream or conversation of messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageDirection {
+  /// Message sent by the consumer to the provider
+  ConsumerToProvider,
+  /// Message sent by the provider to the consumer
+  ProviderToConsumer
+}
+
+impl MessageDirection {
+  fn from_proto(direction: ProtoMessageDirection) -> Option<MessageDirection> {
+    match direction {
+      ProtoMessageDirection::Unspecified => None,
+      ProtoMessageDirection::ConsumerToProvider => Some(MessageDirection::ConsumerToProvider),
+      ProtoMessageDirection::ProviderToConsumer => Some(MessageDirection::ProviderToConsumer)
+    }
+  }
+
+  fn to_proto(direction: Option<MessageDirection>) -> ProtoMessageDirection {
+    match direction {
+      None => ProtoMessageDirection::Unspecified,
+      Some(MessageDirection::ConsumerToProvider) => ProtoMessageDirection::ConsumerToProvider,
+      Some(MessageDirection::ProviderToConsumer) => ProtoMessageDirection::ProviderToConsumer
     }
   }
 }
 
-/// Plugin data to persist into the Pact file
+/// Number of times a message can occur in a stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamCount {
+  /// Minimum number of times the message must occur
+  pub min: u32,
+  /// Maximum number of times the message can occur, or `None` if there is no maximum
+  pub max: Option<u32>
+}
+
+impl From<&ProtoStreamCount> for StreamCount {
+  fn from(count: &ProtoStreamCount) -> Self {
+    StreamCount {
+      min: count.min,
+      max: if count.max == 0 { None } else { Some(count.max) }
+    }
+  }
+}
+
+impl From<&StreamCount> for ProtoStreamCount {
+  fn from(count: &StreamCount) -> Self {
+    ProtoStreamCount {
+      min: count.min,
+      max: count.max.unwrap_or_default()
+    }
+  }
+}
+
+/// Actual message received in a stream of messages
+#[derive(Clone, Debug)]
+pub struct StreamMessage {
+  /// Contents of the message
+  pub contents: OptionalBody,
+  /// Direction of the message
+  pub direction: MessageDirection
+}
+
+/// Mismatch with a stream of messages
 #[derive(Clone, Debug)]
+pub struct StreamMismatch {
+  /// Index of the expected message the mismatch is for
+  pub expected_index: usize,
+  /// Index of the actual message the mismatch is for, or `None` if there was no actual message
+  /// (i.e. an expected message was not received)
+  pub actual_index: Option<usize>,
+  /// Description of the mismatch
+  pub mismatch: String,
+  /// Any mismatches with the contents of the message
+  pub content_mismatches: Vec<ContentMismatch>
+}
+
+impl From<&ProtoStreamMismatch> for StreamMismatch {
+  fn from(mismatch: &ProtoStreamMismatch) -> Self {
+    StreamMismatch {
+      expected_index: mismatch.expected_index as usize,
+      actual_index: usize::try_from(mismatch.actual_index).ok(),
+      mismatch: mismatch.mismatch.clone(),
+      content_mismatches: mismatch.content_mismatches.iter().map(ContentMismatch::from).collect()
+    }
+  }
+}
+
+/// Plugin data to persist into the Pact file
+#[derive(Clone, Debug, Default)]
 pub struct PluginConfiguration {
   /// Data to perist on the interaction
   pub interaction_configuration: HashMap<String, Value>,
@@ -118,20 +349,11 @@ impl PluginConfiguration {
   }
 }
 
-impl Default for PluginConfiguration {
-  fn default() -> Self {
-    PluginConfiguration {
-      interaction_configuration: Default::default(),
-      pact_configuration: Default::default()
-    }
-  }
-}
-
 impl From<ProtoPluginConfiguration> for PluginConfiguration {
   fn from(config: ProtoPluginConfiguration) -> Self {
     PluginConfiguration {
-      interaction_configuration: config.interaction_configuration.as_ref().map(|c| proto_struct_to_map(c)).unwrap_or_default(),
-      pact_configuration: config.pact_configuration.as_ref().map(|c| proto_struct_to_map(c)).unwrap_or_default()
+      interaction_configuration: config.interaction_configuration.as_ref().map(proto_struct_to_map).unwrap_or_default(),
+      pact_configuration: config.pact_configuration.as_ref().map(proto_struct_to_map).unwrap_or_default()
     }
   }
 }
@@ -166,252 +388,556 @@ impl ContentMatcher {
   }
 
   /// Get the plugin to configure the interaction contents for the interaction part based on the
-  /// provided definition
+  /// provided definition. The call is aborted if the cancellation token is cancelled.
+  #[instrument(name = "configure_interaction", skip_all, fields(content_type = %content_type, entry = %self.catalogue_entry.key))]
   pub async fn configure_interation(
     &self,
     content_type: &ContentType,
+    definition: HashMap<String, Value>,
+    cancellation_token: Option<&CancellationToken>
+  ) -> anyhow::Result<(Vec<InteractionContents>, Option<PluginConfiguration>)> {
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(anyhow!("Content matcher '{}' is provided by the core framework, not a plugin",
+        self.catalogue_entry_key()))
+    };
+    match lookup_plugin(&plugin_manifest.as_dependency()) {
+      Some(plugin) => with_cancellation(cancellation_token,
+        self.configure_interaction_with_plugin(&plugin, content_type, definition)).await?,
+      None => {
+        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
+        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+      }
+    }
+  }
+
+  /// Internal function: public for testing. Sends the configure interaction request to the given
+  /// plugin and converts the response, returning an error listing any invalid rules or generators.
+  pub async fn configure_interaction_with_plugin(
+    &self,
+    plugin: &dyn PactPluginRpc,
+    content_type: &ContentType,
     definition: HashMap<String, Value>
   ) -> anyhow::Result<(Vec<InteractionContents>, Option<PluginConfiguration>)> {
     debug!("Sending ConfigureContents request to plugin {:?}", self.catalogue_entry);
     let request = ConfigureInteractionRequest {
       content_type: content_type.to_string(),
       contents_config: Some(to_proto_struct(definition)),
+      .. ConfigureInteractionRequest::default()
     };
 
-    let plugin_manifest = self.catalogue_entry.plugin.as_ref()
-      .expect("Plugin type is required");
+    match plugin.configure_interaction(request).await {
+      Ok(response) => configure_interaction_result(response, self.plugin_name().as_str(),
+        self.plugin_version().as_str()),
+      Err(err) => {
+        error!("Call to plugin failed - {}", err);
+        Err(anyhow!("Call to plugin failed - {}", err))
+      }
+    }
+  }
+
+  /// Get the plugin to match the contents (and any metadata or headers) against the expected
+  /// contents, returning all the mismatches. The call is aborted if the cancellation token is cancelled.
+  #[instrument(skip_all, fields(entry = %self.catalogue_entry.key))]
+  pub async fn match_contents(
+    &self,
+    comparison: &ContentComparison,
+    cancellation_token: Option<&CancellationToken>
+  ) -> Result<(), ContentMatchError> {
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(ContentMatchError::CoreMatcher(self.catalogue_entry_key()))
+    };
     match lookup_plugin(&plugin_manifest.as_dependency()) {
-      Some(plugin) => match plugin.configure_interaction(request).await {
-        Ok(response) => {
-          debug!("Got response: {:?}", response);
-          if response.error.is_empty() {
-            let mut results = vec![];
-
-            for response in response.interaction {
-              let body = match &response.contents {
-                Some(body) => {
-                  let returned_content_type = ContentType::parse(body.content_type.as_str()).ok();
-                  let contents = body.content.as_ref().cloned().unwrap_or_default();
-                  OptionalBody::Present(Bytes::from(contents), returned_content_type,
-                                        Some(match body.content_type_hint() {
-                                          body::ContentTypeHint::Text => ContentTypeHint::TEXT,
-                                          body::ContentTypeHint::Binary => ContentTypeHint::BINARY,
-                                          body::ContentTypeHint::Default => ContentTypeHint::DEFAULT,
-                                        }))
-                },
-                None => OptionalBody::Missing
-              };
-
-              let rules = if !response.rules.is_empty() {
-                Some(MatchingRuleCategory {
-                  name: Category::BODY,
-                  rules: response.rules.iter().map(|(k, rules)| {
-                    // TODO: This is unwrapping the DocPath
-                    (DocPath::new(k).unwrap(), RuleList {
-                      rules: rules.rule.iter().map(|rule| {
-                        // TODO: This is unwrapping the MatchingRule
-                        MatchingRule::create(rule.r#type.as_str(), &rule.values.as_ref().map(|rule| {
-                          proto_struct_to_json(rule)
-                        }).unwrap_or_default()).unwrap()
-                      }).collect(),
-                      rule_logic: RuleLogic::And,
-                      cascaded: false
-                    })
-                  }).collect()
-                })
-              } else {
-                None
-              };
-
-              let generators = if !response.generators.is_empty() {
-                Some(Generators {
-                  categories: hashmap! {
-                    GeneratorCategory::BODY => response.generators.iter().map(|(k, gen)| {
-                      // TODO: This is unwrapping the DocPath
-                      // TODO: This is unwrapping the Generator
-                      (DocPath::new(k).unwrap(), Generator::create(gen.r#type.as_str(),
-                        &gen.values.as_ref().map(|attr| proto_struct_to_json(attr)).unwrap_or_default()).unwrap())
-                    }).collect()
-                  }
-                })
-              } else {
-                None
-              };
-
-              let metadata = response.message_metadata.as_ref().map(|md| proto_struct_to_map(md));
-
-              let plugin_config = if let Some(plugin_configuration) = &response.plugin_configuration {
-                PluginConfiguration {
-                  interaction_configuration: plugin_configuration.interaction_configuration.as_ref()
-                    .map(|val| proto_struct_to_map(val)).unwrap_or_default(),
-                  pact_configuration: plugin_configuration.pact_configuration.as_ref()
-                    .map(|val| proto_struct_to_map(val)).unwrap_or_default()
-                }
-              } else {
-                PluginConfiguration::default()
-              };
-
-              debug!("body={}", body);
-              debug!("rules={:?}", rules);
-              debug!("generators={:?}", generators);
-              debug!("metadata={:?}", metadata);
-              debug!("pluginConfig={:?}", plugin_config);
-
-              results.push(InteractionContents {
-                part_name: response.part_name.clone(),
-                body,
-                rules,
-                generators,
-                metadata,
-                plugin_config,
-                interaction_markup: response.interaction_markup.clone(),
-                interaction_markup_type: match response.interaction_markup_type() {
-                  MarkupType::Html => "HTML".to_string(),
-                  _ => "COMMON_MARK".to_string(),
-                }
-              })
-            }
-
-            Ok((results, response.plugin_configuration.map(|config| PluginConfiguration::from(config))))
-          } else {
-            Err(anyhow!("Request to configure interaction failed: {}", response.error))
-          }
-        }
-        Err(err) => {
-          error!("Call to plugin failed - {}", err);
-          Err(anyhow!("Call to plugin failed - {}", err))
-        }
-      },
+      Some(plugin) => with_cancellation(cancellation_token,
+        self.match_contents_with_plugin(&plugin, comparison)).await?,
       None => {
         error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
-        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+        Err(ContentMatchError::PluginNotFound {
+          name: plugin_manifest.name.clone(),
+          version: plugin_manifest.version.clone()
+        })
       }
     }
   }
 
-  /// Get the plugin to match the contents against the expected contents returning all the mismatches.
-  /// Note that it is an error to call this with a non-plugin (core) content matcher.
-  ///
-  /// panics:
-  /// If called with a core content matcher
-  pub async fn match_contents(
+  /// Internal function: public for testing. Sends the comparison to the given plugin, returning
+  /// all the mismatches.
+  pub async fn match_contents_with_plugin(
+    &self,
+    plugin: &dyn PactPluginRpc,
+    comparison: &ContentComparison
+  ) -> Result<(), ContentMatchError> {
+    match plugin.compare_contents(compare_contents_request(comparison)).await {
+      Ok(response) => compare_contents_result(&response, &comparison.context.name,
+        &comparison.expected, &comparison.actual),
+      Err(err) => {
+        error!("Call to plugin failed - {}", err);
+        Err(ContentMatchError::TransportError(err.to_string()))
+      }
+    }
+  }
+
+  /// Get the plugin to match a stream of actual messages (i.e. from a gRPC streaming call) against
+  /// the expected messages. The call is aborted if the cancellation token is cancelled.
+  pub async fn match_stream(
     &self,
-    expected: &OptionalBody,
-    actual: &OptionalBody,
-    context: &MatchingRuleCategory,
+    expected: &[InteractionContents],
+    actual: &[StreamMessage],
     allow_unexpected_keys: bool,
-    plugin_config: Option<PluginInteractionConfig>
-  ) -> Result<(), HashMap<String, Vec<ContentMismatch>>> {
-    let request = CompareContentsRequest {
-      expected: Some(Body {
-        content_type: expected.content_type().unwrap_or_default().to_string(),
-        content: expected.value().map(|b| b.to_vec()),
-        content_type_hint: body::ContentTypeHint::Default as i32
-      }),
-      actual: Some(Body {
-        content_type: actual.content_type().unwrap_or_default().to_string(),
-        content: actual.value().map(|b| b.to_vec()),
-        content_type_hint: body::ContentTypeHint::Default as i32
-      }),
-      allow_unexpected_keys,
-      rules: context.rules.iter().map(|(k, r)| {
-        (k.to_string(), crate::proto::MatchingRules {
-          rule: r.rules.iter().map(|rule|{
-            crate::proto::MatchingRule {
-              r#type: rule.name(),
-              values: Some(to_proto_struct(rule.values().iter().map(|(k, v)| (k.to_string(), v.clone())).collect())),
-            }
-          }).collect()
+    plugin_config: Option<PluginInteractionConfig>,
+    cancellation_token: Option<&CancellationToken>
+  ) -> Result<(), ContentMatchError> {
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(ContentMatchError::CoreMatcher(self.catalogue_entry_key()))
+    };
+    match lookup_plugin(&plugin_manifest.as_dependency()) {
+      Some(plugin) => with_cancellation(cancellation_token,
+        self.match_stream_with_plugin(&plugin, expected, actual, allow_unexpected_keys, plugin_config)).await?,
+      None => {
+        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
+        Err(ContentMatchError::PluginNotFound {
+          name: plugin_manifest.name.clone(),
+          version: plugin_manifest.version.clone()
         })
+      }
+    }
+  }
+
+  /// Internal function: public for testing. Sends the compare stream request to the given plugin.
+  pub async fn match_stream_with_plugin(
+    &self,
+    plugin: &dyn PactPluginRpc,
+    expected: &[InteractionContents],
+    actual: &[StreamMessage],
+    allow_unexpected_keys: bool,
+    plugin_config: Option<PluginInteractionConfig>
+  ) -> Result<(), ContentMatchError> {
+    if !plugin.supports_for_entry(CAPABILITY_COMPARE_STREAM, &self.catalogue_entry) {
+      error!("Plugin for {:?} does not support comparing streams of messages", self.catalogue_entry);
+      return Err(ContentMatchError::PluginError(
+        format!("Plugin for {} does not support comparing streams of messages", self.catalogue_entry.key)));
+    }
+
+    let request = CompareStreamRequest {
+      expected: expected.iter().map(|contents| ExpectedStreamMessage {
+        contents: Some(to_proto_body(&contents.body)),
+        direction: MessageDirection::to_proto(contents.direction) as i32,
+        count: contents.count.as_ref().map(ProtoStreamCount::from),
+        rules: contents.rules.as_ref().map(to_proto_rules).unwrap_or_default()
+      }).collect(),
+      actual: actual.iter().map(|message| ProtoStreamMessage {
+        contents: Some(to_proto_body(&message.contents)),
+        direction: MessageDirection::to_proto(Some(message.direction)) as i32
       }).collect(),
+      allow_unexpected_keys,
       plugin_configuration: plugin_config.map(|config| ProtoPluginConfiguration {
         interaction_configuration: Some(to_proto_struct(config.interaction_configuration)),
         pact_configuration: Some(to_proto_struct(config.pact_configuration))
       })
     };
 
-    let plugin_manifest = self.catalogue_entry.plugin.as_ref()
-      .expect("Plugin type is required");
+    debug!("Sending CompareStream request with {} expected and {} actual messages to plugin {:?}",
+      expected.len(), actual.len(), self.catalogue_entry);
+    match plugin.compare_stream(request).await {
+      Ok(response) => if !response.error.is_empty() {
+        Err(ContentMatchError::PluginError(response.error.clone()))
+      } else if response.mismatches.is_empty() {
+        Ok(())
+      } else {
+        Err(ContentMatchError::StreamMismatches(response.mismatches.iter().map(StreamMismatch::from).collect()))
+      },
+      Err(err) => {
+        error!("Call to plugin failed - {}", err);
+        Err(ContentMatchError::TransportError(err.to_string()))
+      }
+    }
+  }
+
+  /// Get the plugin to match a number of contents, returning the result of each comparison keyed
+  /// by the comparison ID. The call is aborted if the cancellation token is cancelled.
+  pub async fn match_contents_batch(
+    &self,
+    comparisons: &[ContentComparison],
+    cancellation_token: Option<&CancellationToken>
+  ) -> Vec<(String, Result<(), ContentMatchError>)> {
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return comparisons.iter()
+        .map(|comparison| (comparison.id.clone(), Err(ContentMatchError::CoreMatcher(self.catalogue_entry_key()))))
+        .collect()
+    };
     match lookup_plugin(&plugin_manifest.as_dependency()) {
-      Some(plugin) => match plugin.compare_contents(request).await {
-        Ok(response) => if let Some(mismatch) = response.type_mismatch {
-          Err(hashmap!{
-            String::default() => vec![
-              ContentMismatch {
-                expected: mismatch.expected.clone(),
-                actual: mismatch.actual.clone(),
-                mismatch: format!("Expected content type '{}' but got '{}'", mismatch.expected, mismatch.actual),
-                path: "".to_string(),
-                diff: None
-              }
-            ]
-          })
-        } else if !response.error.is_empty() {
-          Err(hashmap! {
-            String::default() => vec![
-              ContentMismatch {
-                expected: Default::default(),
-                actual: Default::default(),
-                mismatch: response.error.clone(),
-                path: "".to_string(),
-                diff: None
-              }
-            ]
-          })
-        } else if !response.results.is_empty() {
-          Err(response.results.iter().map(|(k, v)| {
-            (k.clone(), v.mismatches.iter().map(|mismatch| {
-              ContentMismatch {
-                expected: mismatch.expected.as_ref()
-                  .map(|e| from_utf8(&e).unwrap_or_default().to_string())
-                  .unwrap_or_default(),
-                actual: mismatch.actual.as_ref()
-                  .map(|a| from_utf8(&a).unwrap_or_default().to_string())
-                  .unwrap_or_default(),
-                mismatch: mismatch.mismatch.clone(),
-                path: mismatch.path.clone(),
-                diff: if mismatch.diff.is_empty() {
-                  None
-                } else {
-                  Some(mismatch.diff.clone())
-                }
-              }
-            }).collect())
-          }).collect())
-        } else {
-          Ok(())
-        }
-        Err(err) => {
-          error!("Call to plugin failed - {}", err);
-          Err(hashmap! {
-            String::default() => vec![
-              ContentMismatch {
-                expected: "".to_string(),
-                actual: "".to_string(),
-                mismatch: format!("Call to plugin failed = {}", err),
-                path: "".to_string(),
-                diff: None
-              }
-            ]
-          })
-        }
+      Some(plugin) => match with_cancellation(cancellation_token,
+        self.match_contents_batch_with_plugin(&plugin, comparisons)).await {
+        Ok(results) => results,
+        Err(_) => comparisons.iter()
+          .map(|comparison| (comparison.id.clone(), Err(ContentMatchError::Cancelled)))
+          .collect()
       },
       None => {
         error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
-        Err(hashmap! {
-          String::default() => vec![
-            ContentMismatch {
-              expected: "".to_string(),
-              actual: "".to_string(),
-              mismatch: format!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry),
-              path: "".to_string(),
-              diff: None
-            }
-          ]
-        })
+        comparisons.iter().map(|comparison| {
+          (comparison.id.clone(), Err(ContentMatchError::PluginNotFound {
+            name: plugin_manifest.name.clone(),
+            version: plugin_manifest.version.clone()
+          }))
+        }).collect()
       }
     }
   }
+
+  /// Internal function: public for testing. Sends the comparisons to the given plugin, in a single
+  /// request if the plugin supports batches.
+  pub async fn match_contents_batch_with_plugin(
+    &self,
+    plugin: &dyn PactPluginRpc,
+    comparisons: &[ContentComparison]
+  ) -> Vec<(String, Result<(), ContentMatchError>)> {
+    if plugin.supports_for_entry(CAPABILITY_COMPARE_CONTENTS_BATCH, &self.catalogue_entry) {
+      debug!("Sending CompareContentsBatch request with {} comparisons to plugin {:?}",
+        comparisons.len(), self.catalogue_entry);
+      let request = CompareContentsBatchRequest {
+        comparisons: comparisons.iter().map(|comparison| compare_contents_batch_request::Comparison {
+          id: comparison.id.clone(),
+          request: Some(compare_contents_request(comparison))
+        }).collect()
+      };
+      match plugin.compare_contents_batch(request).await {
+        Ok(response) => comparisons.iter().map(|comparison| {
+          let result = match response.results.iter().find(|result| result.id == comparison.id) {
+            Some(result) => compare_contents_result(&result.response.clone().unwrap_or_default(),
+              &comparison.context.name, &comparison.expected, &comparison.actual),
+            None => Err(ContentMatchError::PluginError(
+              format!("Plugin did not return a result for comparison '{}'", comparison.id)))
+          };
+          (comparison.id.clone(), result)
+        }).collect(),
+        Err(err) => {
+          error!("Call to plugin failed - {}", err);
+          comparisons.iter()
+            .map(|comparison| (comparison.id.clone(), Err(ContentMatchError::TransportError(err.to_string()))))
+            .collect()
+        }
+      }
+    } else {
+      let mut results = vec![];
+      for comparison in comparisons {
+        results.push((comparison.id.clone(), self.match_contents_with_plugin(plugin, comparison).await));
+      }
+      results
+    }
+  }
+}
+
+/// Contents to compare, either on their own or as part of a batch of comparisons
+#[derive(Clone, Debug)]
+pub struct ContentComparison {
+  /// ID used to correlate the result with this comparison in a batch
+  pub id: String,
+  /// Expected contents
+  pub expected: OptionalBody,
+  /// Actual contents
+  pub actual: OptionalBody,
+  /// Matching rules to apply
+  pub context: MatchingRuleCategory,
+  /// Message metadata or HTTP headers to match along with the contents
+  pub metadata: ContentMetadata,
+  /// If unexpected keys are allowed
+  pub allow_unexpected_keys: bool,
+  /// Plugin configuration stored with the Pact and interaction
+  pub plugin_config: Option<PluginInteractionConfig>
+}
+
+/// Message metadata (for message interactions) or HTTP headers (for HTTP interactions) to send to
+/// the plugin to match along with the contents
+#[derive(Clone, Debug, Default)]
+pub struct ContentMetadata {
+  /// Expected message metadata
+  pub expected_metadata: HashMap<String, Value>,
+  /// Actual message metadata
+  pub actual_metadata: HashMap<String, Value>,
+  /// Matching rules for the message metadata (METADATA category)
+  pub metadata_rules: Option<MatchingRuleCategory>,
+  /// Expected HTTP headers
+  pub expected_headers: HashMap<String, Vec<String>>,
+  /// Actual HTTP headers
+  pub actual_headers: HashMap<String, Vec<String>>,
+  /// Matching rules for the HTTP headers (HEADER category)
+  pub header_rules: Option<MatchingRuleCategory>
+}
+
+/// Converts the response to a configure interaction request. Any invalid matching rules or
+/// generators returned by the plugin will result in an error listing all of them.
+pub(crate) fn configure_interaction_result(
+  response: ConfigureInteractionResponse,
+  plugin_name: &str,
+  plugin_version: &str
+) -> anyhow::Result<(Vec<InteractionContents>, Option<PluginConfiguration>)> {
+  debug!("Got response: {:?}", response);
+  if response.error.is_empty() {
+    let mut results = vec![];
+    let mut errors = vec![];
+
+    for response in response.interaction {
+      let body = match &response.contents {
+        Some(body) => {
+          let returned_content_type = ContentType::parse(body.content_type.as_str()).ok();
+          let contents = body.content.as_ref().cloned().unwrap_or_default();
+          OptionalBody::Present(Bytes::from(contents), returned_content_type,
+                                Some(content_type_hint_from_proto(body.content_type_hint())))
+        },
+        None => OptionalBody::Missing
+      };
+
+      let mut part_errors = vec![];
+      let rules = convert_matching_rules(&response.rules, &mut part_errors);
+      let generators = convert_generators(&response.generators, &mut part_errors);
+      if response.part_name.is_empty() {
+        errors.extend(part_errors);
+      } else {
+        errors.extend(part_errors.iter().map(|err| format!("{} (part '{}')", err, response.part_name)));
+      }
+
+      let metadata = response.message_metadata.as_ref().map(proto_struct_to_map);
+
+      let plugin_config = if let Some(plugin_configuration) = &response.plugin_configuration {
+        PluginConfiguration {
+          interaction_configuration: plugin_configuration.interaction_configuration.as_ref()
+            .map(proto_struct_to_map).unwrap_or_default(),
+          pact_configuration: plugin_configuration.pact_configuration.as_ref()
+            .map(proto_struct_to_map).unwrap_or_default()
+        }
+      } else {
+        PluginConfiguration::default()
+      };
+
+      let interaction_fields = response.interaction_fields.as_ref()
+        .map(proto_struct_to_map)
+        .unwrap_or_default();
+
+      debug!("body={}", body);
+      debug!("rules={:?}", rules);
+      debug!("generators={:?}", generators);
+      debug!("metadata={:?}", metadata);
+      debug!("pluginConfig={:?}", plugin_config);
+      debug!("interactionFields={:?}", interaction_fields);
+
+      results.push(InteractionContents {
+        part_name: response.part_name.clone(),
+        body,
+        rules,
+        generators,
+        metadata,
+        plugin_config,
+        interaction_markup: response.interaction_markup.clone(),
+        interaction_markup_type: MarkupType::from_proto(response.interaction_markup_type()),
+        interaction_fields,
+        direction: MessageDirection::from_proto(response.direction()),
+        sequence: response.sequence,
+        count: response.count.as_ref().map(StreamCount::from)
+      })
+    }
+
+    if errors.is_empty() {
+      results.sort_by_key(|contents| contents.sequence);
+      Ok((results, response.plugin_configuration.map(PluginConfiguration::from)))
+    } else {
+      error!("Plugin {}/{} returned invalid matching rules or generators: {:?}", plugin_name,
+        plugin_version, errors);
+      Err(anyhow!("Plugin {}/{} returned invalid matching rules or generators:\n  {}", plugin_name,
+        plugin_version, errors.join("\n  ")))
+    }
+  } else {
+    Err(anyhow!("Request to configure interaction failed: {}", response.error))
+  }
+}
+
+fn compare_contents_request(comparison: &ContentComparison) -> CompareContentsRequest {
+  let metadata = &comparison.metadata;
+  CompareContentsRequest {
+    expected: Some(to_proto_body(&comparison.expected)),
+    actual: Some(to_proto_body(&comparison.actual)),
+    allow_unexpected_keys: comparison.allow_unexpected_keys,
+    rules: to_proto_rules(&comparison.context),
+    plugin_configuration: comparison.plugin_config.clone().map(|config| ProtoPluginConfiguration {
+      interaction_configuration: Some(to_proto_struct(config.interaction_configuration)),
+      pact_configuration: Some(to_proto_struct(config.pact_configuration))
+    }),
+    expected_metadata: to_proto_metadata(&metadata.expected_metadata),
+    actual_metadata: to_proto_metadata(&metadata.actual_metadata),
+    metadata_rules: metadata.metadata_rules.as_ref().map(to_proto_rules).unwrap_or_default(),
+    expected_headers: to_proto_headers(&metadata.expected_headers),
+    actual_headers: to_proto_headers(&metadata.actual_headers),
+    header_rules: metadata.header_rules.as_ref().map(to_proto_rules).unwrap_or_default()
+  }
+}
+
+pub(crate) fn from_proto_body(body: Option<&Body>) -> OptionalBody {
+  match body {
+    Some(body) => match &body.content {
+      Some(contents) => OptionalBody::Present(Bytes::from(contents.clone()),
+        ContentType::parse(body.content_type.as_str()).ok(),
+        Some(content_type_hint_from_proto(body.content_type_hint()))),
+      None => OptionalBody::Missing
+    },
+    None => OptionalBody::Missing
+  }
+}
+
+pub(crate) fn to_proto_body(body: &OptionalBody) -> Body {
+  Body {
+    content_type: body.content_type().unwrap_or_default().to_string(),
+    content: body.value().map(|b| b.to_vec()),
+    content_type_hint: content_type_hint_to_proto(body) as i32
+  }
+}
+
+fn to_proto_rules(rules: &MatchingRuleCategory) -> HashMap<String, ProtoMatchingRules> {
+  rules.rules.iter().map(|(k, r)| {
+    (k.to_string(), ProtoMatchingRules {
+      rule: r.rules.iter().map(|rule|{
+        crate::proto::MatchingRule {
+          r#type: rule.name(),
+          values: Some(to_proto_struct(rule.values().iter().map(|(k, v)| (k.to_string(), v.clone())).collect())),
+        }
+      }).collect()
+    })
+  }).collect()
+}
+
+fn to_proto_metadata(metadata: &HashMap<String, Value>) -> Option<prost_types::Struct> {
+  if metadata.is_empty() {
+    None
+  } else {
+    Some(to_proto_struct(metadata.clone()))
+  }
+}
+
+fn to_proto_headers(headers: &HashMap<String, Vec<String>>) -> Option<prost_types::Struct> {
+  if headers.is_empty() {
+    None
+  } else {
+    Some(to_proto_struct(headers.iter()
+      .map(|(k, v)| (k.clone(), Value::Array(v.iter().map(|v| Value::String(v.clone())).collect())))
+      .collect()))
+  }
+}
+
+fn compare_contents_result(
+  response: &CompareContentsResponse,
+  body_category: &Category,
+  expected: &OptionalBody,
+  actual: &OptionalBody
+) -> Result<(), ContentMatchError> {
+  if let Some(mismatch) = &response.type_mismatch {
+    Err(ContentMatchError::TypeMismatch {
+      expected: mismatch.expected.clone(),
+      actual: mismatch.actual.clone()
+    })
+  } else if !response.error.is_empty() {
+    Err(ContentMatchError::PluginError(response.error.clone()))
+  } else {
+    let mismatches: HashMap<Category, HashMap<String, Vec<ContentMismatch>>> = vec![
+      (body_category.clone(), add_body_diff(convert_mismatches(&response.results), expected, actual)),
+      (Category::METADATA, convert_mismatches(&response.metadata_results)),
+      (Category::HEADER, convert_mismatches(&response.header_results))
+    ].into_iter()
+      .filter(|(_, mismatches)| !mismatches.is_empty())
+      .collect();
+    if mismatches.is_empty() {
+      Ok(())
+    } else {
+      Err(ContentMatchError::Mismatches(mismatches))
+    }
+  }
+}
+
+fn convert_mismatches(results: &HashMap<String, ContentMismatches>) -> HashMap<String, Vec<ContentMismatch>> {
+  results.iter()
+    .filter(|(_, v)| !v.mismatches.is_empty())
+    .map(|(k, v)| (k.clone(), v.mismatches.iter().map(ContentMismatch::from).collect()))
+    .collect()
+}
+
+// Plugins don't have to provide a diff, so if none of the body mismatches have one, a diff of the
+// whole expected and actual bodies is added to the first mismatch (by path), so it is only displayed once
+fn add_body_diff(
+  mut mismatches: HashMap<String, Vec<ContentMismatch>>,
+  expected: &OptionalBody,
+  actual: &OptionalBody
+) -> HashMap<String, Vec<ContentMismatch>> {
+  if mismatches.values().flatten().all(|mismatch| mismatch.diff.is_none()) {
+    let first_path = mismatches.keys().min().cloned();
+    if let Some(mismatch) = first_path.and_then(|path| mismatches.get_mut(&path)).and_then(|m| m.first_mut()) {
+      mismatch.diff = generate_diff(expected.content_type().as_ref(), &expected.value().unwrap_or_default(),
+        &actual.value().unwrap_or_default());
+    }
+  }
+  mismatches
+}
+
+pub(crate) fn convert_matching_rules(
+  rules: &HashMap<String, ProtoMatchingRules>,
+  errors: &mut Vec<String>
+) -> Option<MatchingRuleCategory> {
+  if rules.is_empty() {
+    None
+  } else {
+    Some(MatchingRuleCategory {
+      name: Category::BODY,
+      rules: rules.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)).filter_map(|(path, rules)| {
+        let rules: Vec<MatchingRule> = rules.rule.iter().filter_map(|rule| {
+          let values = rule.values.as_ref().map(proto_struct_to_json).unwrap_or_default();
+          MatchingRule::create(rule.r#type.as_str(), &values)
+            .map_err(|err| errors.push(format!("Matching rule '{}' for path '{}' is not valid - {}",
+              rule.r#type, path, err)))
+            .ok()
+        }).collect();
+        DocPath::new(path)
+          .map_err(|err| errors.push(format!("Matching rule path '{}' is not valid - {}", path, err)))
+          .ok()
+          .map(|path| (path, RuleList { rules, rule_logic: RuleLogic::And, cascaded: false }))
+      }).collect()
+    })
+  }
+}
+
+fn convert_generators(
+  generators: &HashMap<String, ProtoGenerator>,
+  errors: &mut Vec<String>
+) -> Option<Generators> {
+  if generators.is_empty() {
+    None
+  } else {
+    Some(Generators {
+      categories: hashmap! {
+        GeneratorCategory::BODY => generators.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)).filter_map(|(path, gen)| {
+          let values = gen.values.as_ref().map(proto_struct_to_json).unwrap_or_default();
+          let generator = Generator::create(gen.r#type.as_str(), &values)
+            .map_err(|err| errors.push(format!("Generator '{}' for path '{}' is not valid - {}",
+              gen.r#type, path, err)))
+            .ok();
+          let path = DocPath::new(path)
+            .map_err(|err| errors.push(format!("Generator path '{}' is not valid - {}", path, err)))
+            .ok();
+          path.zip(generator)
+        }).collect()
+      }
+    })
+  }
+}
+
+/// Options passed on to the plugin when generating contents
+#[derive(Clone, Debug)]
+pub struct GenerateContentOptions<'a> {
+  /// Plugin configuration stored with the Pact and interaction
+  pub plugin_config: Option<PluginInteractionConfig>,
+  /// If the generators are being applied for a consumer or provider test
+  pub test_mode: GeneratorTestMode,
+  /// Context values for the generators (i.e. values from provider states)
+  pub context: HashMap<&'a str, Value>
 }
 
 /// Generator for contents based on content type
@@ -443,19 +969,47 @@ impl ContentGenerator {
       .unwrap_or("core".to_string())
   }
 
-  /// Generate the content for the given content type and body
-  // TODO need to pass in any plugin configuration
+  /// Generate the content for the given content type and body. The call is aborted if the
+  /// cancellation token is cancelled.
+  #[instrument(skip_all, fields(content_type = %content_type, entry = %self.catalogue_entry.key))]
   pub async fn generate_content(
     &self,
     content_type: &ContentType,
     generators: &HashMap<String, Generator>,
-    body: &OptionalBody
+    body: &OptionalBody,
+    options: &GenerateContentOptions<'_>,
+    cancellation_token: Option<&CancellationToken>
+  ) -> anyhow::Result<OptionalBody> {
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(anyhow!("Content generator '{}' is provided by the core framework, not a plugin",
+        self.catalogue_entry_key()))
+    };
+    match lookup_plugin(&plugin_manifest.as_dependency()) {
+      Some(plugin) => with_cancellation(cancellation_token, self.generate_content_with_plugin(&plugin,
+        content_type, generators, body, options)).await?,
+      None => {
+        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
+        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+      }
+    }
+  }
+
+  /// Internal function: public for testing. Sends the generate content request to the given
+  /// plugin and converts the response.
+  pub async fn generate_content_with_plugin(
+    &self,
+    plugin: &dyn PactPluginRpc,
+    content_type: &ContentType,
+    generators: &HashMap<String, Generator>,
+    body: &OptionalBody,
+    options: &GenerateContentOptions<'_>
   ) -> anyhow::Result<OptionalBody> {
     let request = GenerateContentRequest {
       contents: Some(crate::proto::Body {
         content_type: content_type.to_string(),
         content: Some(body.value().unwrap_or_default().to_vec()),
-        content_type_hint: body::ContentTypeHint::Default as i32
+        content_type_hint: content_type_hint_to_proto(body) as i32
       }),
       generators: generators.iter().map(|(k, v)| {
         (k.clone(), crate::proto::Generator {
@@ -464,29 +1018,52 @@ impl ContentGenerator {
             .map(|(k, v)| (k.to_string(), v.clone())).collect())),
         })
       }).collect(),
-      plugin_configuration: None
+      plugin_configuration: options.plugin_config.clone().map(|config| ProtoPluginConfiguration {
+        interaction_configuration: Some(to_proto_struct(config.interaction_configuration)),
+        pact_configuration: Some(to_proto_struct(config.pact_configuration))
+      }),
+      test_mode: match options.test_mode {
+        GeneratorTestMode::Consumer => generate_content_request::TestMode::Consumer as i32,
+        GeneratorTestMode::Provider => generate_content_request::TestMode::Provider as i32
+      },
+      test_context: Some(to_proto_struct(options.context.iter()
+        .map(|(k, v)| (k.to_string(), v.clone())).collect()))
     };
 
-    let plugin_manifest = self.catalogue_entry.plugin.as_ref()
-      .expect("Plugin type is required");
-    match lookup_plugin(&plugin_manifest.as_dependency()) {
-      Some(plugin) => {
-        debug!("Sending generateContent request to plugin {:?}", plugin_manifest);
-        match plugin.generate_content(request).await?.contents {
-          Some(contents) => {
-            Ok(OptionalBody::Present(
-              Bytes::from(contents.content.unwrap_or_default()),
-              ContentType::parse(contents.content_type.as_str()).ok(),
-              None
-            ))
-          }
-          None => Ok(OptionalBody::Empty)
-        }
-      },
-      None => {
-        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
-        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+    debug!("Sending generateContent request to plugin {:?}", self.catalogue_entry);
+    match plugin.generate_content(request).await?.contents {
+      Some(contents) => {
+        // Plugins that don't set a hint get the hint of the original body
+        let hint = match contents.content_type_hint() {
+          body::ContentTypeHint::Default => match body {
+            OptionalBody::Present(_, _, hint) => *hint,
+            _ => None
+          },
+          hint => Some(content_type_hint_from_proto(hint))
+        };
+        Ok(OptionalBody::Present(
+          Bytes::from(contents.content.unwrap_or_default()),
+          ContentType::parse(contents.content_type.as_str()).ok(),
+          hint
+        ))
       }
+      None => Ok(OptionalBody::Empty)
     }
   }
 }
+
+pub(crate) fn content_type_hint_to_proto(body: &OptionalBody) -> body::ContentTypeHint {
+  match body {
+    OptionalBody::Present(_, _, Some(ContentTypeHint::TEXT)) => body::ContentTypeHint::Text,
+    OptionalBody::Present(_, _, Some(ContentTypeHint::BINARY)) => body::ContentTypeHint::Binary,
+    _ => body::ContentTypeHint::Default
+  }
+}
+
+pub(crate) fn content_type_hint_from_proto(hint: body::ContentTypeHint) -> ContentTypeHint {
+  match hint {
+    body::ContentTypeHint::Text => ContentTypeHint::TEXT,
+    body::ContentTypeHint::Binary => ContentTypeHint::BINARY,
+    body::ContentTypeHint::Default => ContentTypeHint::DEFAULT
+  }
+}
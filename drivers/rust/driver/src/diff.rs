@@ -0,0 +1,114 @@
+//! Functions for generating diffs of contents for display with mismatches
+
+use std::str::from_utf8;
+
+use csv::ReaderBuilder;
+use difference::{Changeset, Difference};
+use pact_models::content_types::ContentType;
+
+/// Generates a line based diff of the expected and actual text. Each line is prefixed with `-` if
+/// it is only in the expected text, `+` if it is only in the actual text, or a space if it is in both.
+pub fn line_diff(expected: &str, actual: &str) -> String {
+  let changeset = Changeset::new(expected, actual, "\n");
+  let mut lines = vec![];
+  for diff in &changeset.diffs {
+    let (prefix, text) = match diff {
+      Difference::Same(text) => (' ', text),
+      Difference::Rem(text) => ('-', text),
+      Difference::Add(text) => ('+', text)
+    };
+    for line in text.split('\n') {
+      lines.push(format!("{}{}", prefix, line));
+    }
+  }
+  lines.join("\n")
+}
+
+/// Generates a structural diff of tabular data (i.e. rows of CSV). For each row that is different,
+/// the expected and actual row are displayed, followed by each column that is different. Columns
+/// are numbered from 1.
+pub fn table_diff<S: AsRef<str>>(expected: &[Vec<S>], actual: &[Vec<S>]) -> String {
+  let mut lines = vec![];
+  let row_count = expected.len().max(actual.len());
+  for row in 0..row_count {
+    match (expected.get(row), actual.get(row)) {
+      (Some(expected_row), Some(actual_row)) => {
+        let column_count = expected_row.len().max(actual_row.len());
+        let columns: Vec<String> = (0..column_count).filter_map(|col| {
+          let expected_value = expected_row.get(col).map(|v| v.as_ref());
+          let actual_value = actual_row.get(col).map(|v| v.as_ref());
+          if expected_value != actual_value {
+            Some(format!("  column {}: {} -> {}", col + 1, display_cell(expected_value), display_cell(actual_value)))
+          } else {
+            None
+          }
+        }).collect();
+        if !columns.is_empty() {
+          lines.push(format!("-row {}: {}", row + 1, join_row(expected_row)));
+          lines.push(format!("+row {}: {}", row + 1, join_row(actual_row)));
+          lines.extend(columns);
+        }
+      }
+      (Some(expected_row), None) => lines.push(format!("-row {}: {}", row + 1, join_row(expected_row))),
+      (None, Some(actual_row)) => lines.push(format!("+row {}: {}", row + 1, join_row(actual_row))),
+      (None, None) => {}
+    }
+  }
+  lines.join("\n")
+}
+
+/// Generates a diff of the expected and actual contents (i.e. bodies), based on the content type.
+/// Tabular content types (CSV and TSV) will get a row and column diff, other text content (or tabular
+/// content that can not be parsed) will get a line based diff. Returns `None` if the contents are
+/// the same, or are binary.
+pub fn generate_diff(content_type: Option<&ContentType>, expected: &[u8], actual: &[u8]) -> Option<String> {
+  if expected == actual {
+    return None;
+  }
+
+  let (expected, actual) = match (from_utf8(expected), from_utf8(actual)) {
+    (Ok(expected), Ok(actual)) => (expected, actual),
+    _ => return None
+  };
+  if content_type.map(|ct| ct.is_binary()).unwrap_or(false) {
+    return None;
+  }
+
+  let tables = content_type.and_then(table_delimiter)
+    .and_then(|delimiter| parse_table(expected, delimiter).zip(parse_table(actual, delimiter)));
+  match tables {
+    Some((expected, actual)) => Some(table_diff(&expected, &actual)),
+    None => Some(line_diff(expected, actual))
+  }
+}
+
+fn table_delimiter(content_type: &ContentType) -> Option<u8> {
+  match (content_type.main_type.as_str(), content_type.sub_type.as_str()) {
+    ("text", "csv") | ("application", "csv") => Some(b','),
+    ("text", "tab-separated-values") => Some(b'\t'),
+    _ => None
+  }
+}
+
+fn parse_table(contents: &str, delimiter: u8) -> Option<Vec<Vec<String>>> {
+  ReaderBuilder::new()
+    .has_headers(false)
+    .flexible(true)
+    .delimiter(delimiter)
+    .from_reader(contents.as_bytes())
+    .records()
+    .map(|record| record.map(|record| record.iter().map(|value| value.to_string()).collect()))
+    .collect::<Result<_, _>>()
+    .ok()
+}
+
+fn join_row<S: AsRef<str>>(row: &[S]) -> String {
+  row.iter().map(|v| v.as_ref()).collect::<Vec<_>>().join(",")
+}
+
+fn display_cell(value: Option<&str>) -> String {
+  match value {
+    Some(value) => format!("'{}'", value),
+    None => "<missing>".to_string()
+  }
+}
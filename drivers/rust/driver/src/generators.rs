@@ -0,0 +1,119 @@
+//! Support for generators provided by plugins
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use log::{debug, error};
+use serde_json::Value;
+
+use crate::cancellation::{CancellationToken, with_cancellation};
+use crate::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType, find_generator};
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::{PactPluginManifest, PactPluginRpc, PluginInteractionConfig};
+use crate::proto::{GenerateValueRequest, PluginConfiguration as ProtoPluginConfiguration};
+use crate::utils::{proto_value_to_json, to_proto_struct};
+
+/// Generator for individual values provided by a plugin (or the core framework)
+#[derive(Clone, Debug)]
+pub struct PluginGenerator {
+  /// Catalogue entry for this generator
+  pub catalogue_entry: CatalogueEntry
+}
+
+impl PluginGenerator {
+  /// Plugin details for this generator
+  pub fn plugin(&self) -> Option<PactPluginManifest> {
+    self.catalogue_entry.plugin.clone()
+  }
+
+  /// If this is a core framework generator
+  pub fn is_core(&self) -> bool {
+    self.catalogue_entry.provider_type == CatalogueEntryProviderType::CORE
+  }
+
+  /// Plugin name that provides this generator
+  pub fn plugin_name(&self) -> String {
+    self.catalogue_entry.plugin.as_ref()
+      .map(|p| p.name.clone())
+      .unwrap_or("core".to_string())
+  }
+
+  /// Plugin version that provides this generator
+  pub fn plugin_version(&self) -> String {
+    self.catalogue_entry.plugin.as_ref()
+      .map(|p| p.version.clone())
+      .unwrap_or_default()
+  }
+
+  /// Name of the generator. This is the key the plugin registered the generator with.
+  pub fn name(&self) -> String {
+    match &self.catalogue_entry.plugin {
+      Some(plugin) => self.catalogue_entry.key
+        .strip_prefix(format!("plugin/{}/generator/", plugin.name).as_str())
+        .unwrap_or(self.catalogue_entry.key.as_str())
+        .to_string(),
+      None => self.catalogue_entry.key.clone()
+    }
+  }
+
+  /// Get the plugin to generate a value using the generator configuration and context values
+  /// (i.e. values from provider states). If the cancellation token is cancelled before the plugin
+  /// responds, the call to the plugin is aborted and an error returned.
+  pub async fn generate_value(
+    &self,
+    config: &HashMap<String, Value>,
+    context: &HashMap<&str, Value>,
+    plugin_config: Option<PluginInteractionConfig>,
+    cancellation_token: Option<&CancellationToken>
+  ) -> anyhow::Result<Value> {
+    let request = GenerateValueRequest {
+      name: self.name(),
+      values: Some(to_proto_struct(config.clone())),
+      context: Some(to_proto_struct(context.iter()
+        .map(|(k, v)| (k.to_string(), v.clone())).collect())),
+      plugin_configuration: plugin_config.map(|config| ProtoPluginConfiguration {
+        interaction_configuration: Some(to_proto_struct(config.interaction_configuration)),
+        pact_configuration: Some(to_proto_struct(config.pact_configuration))
+      })
+    };
+
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(anyhow!("Generator '{}' is provided by the core framework, not a plugin", self.name()))
+    };
+    match lookup_plugin(&plugin_manifest.as_dependency()) {
+      Some(plugin) => {
+        debug!("Sending generateValue request to plugin {:?}", plugin_manifest);
+        let response = with_cancellation(cancellation_token, plugin.generate_value(request)).await??;
+        if response.error.is_empty() {
+          Ok(response.value.as_ref().map(proto_value_to_json).unwrap_or_default())
+        } else {
+          Err(anyhow!("Plugin failed to generate a value using generator '{}': {}", self.name(), response.error))
+        }
+      },
+      None => {
+        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
+        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+      }
+    }
+  }
+}
+
+/// Generate a value using a generator provided by a plugin. This will look up the generator from
+/// the catalogue, and then get the plugin that provides it to generate the value. This allows
+/// core content generators (i.e. for JSON bodies) to use generators provided by plugins.
+pub async fn generate_value(
+  name: &str,
+  config: &HashMap<String, Value>,
+  context: &HashMap<&str, Value>,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<Value> {
+  match find_generator(name) {
+    Some(generator) => if generator.is_core() {
+      Err(anyhow!("Generator '{}' is provided by the core framework, not a plugin", name))
+    } else {
+      generator.generate_value(config, context, None, cancellation_token).await
+    },
+    None => Err(anyhow!("No plugin provides a generator '{}'", name))
+  }
+}
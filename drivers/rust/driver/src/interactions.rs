@@ -0,0 +1,80 @@
+//! Support for interaction types provided by plugins (i.e. Kafka produce/consume interactions)
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use log::{debug, error};
+use pact_models::content_types::ContentType;
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::cancellation::{CancellationToken, with_cancellation};
+use crate::catalogue_manager::CatalogueEntry;
+use crate::content::{configure_interaction_result, InteractionContents, PluginConfiguration};
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::{PactPluginManifest, PactPluginRpc};
+use crate::proto::ConfigureInteractionRequest;
+use crate::utils::to_proto_struct;
+
+/// Get the plugin that owns the interaction type (see `find_interaction_type`) to configure an
+/// interaction of that type based on the provided definition. As well as the contents, the plugin
+/// can return interaction level fields to set (`InteractionContents.interaction_fields`). The call
+/// is aborted if the cancellation token is cancelled.
+#[instrument(skip_all, fields(interaction_type = %interaction_type.key))]
+pub async fn configure_interaction_type(
+  interaction_type: &CatalogueEntry,
+  content_type: Option<&ContentType>,
+  definition: HashMap<String, Value>,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<(Vec<InteractionContents>, Option<PluginConfiguration>)> {
+  let plugin_manifest = interaction_type_plugin(interaction_type)?;
+  match lookup_plugin(&plugin_manifest.as_dependency()) {
+    Some(plugin) => with_cancellation(cancellation_token,
+      configure_interaction_type_with_plugin(&plugin, interaction_type, content_type, definition)).await?,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", interaction_type);
+      Err(anyhow!("Plugin for {:?} was not found in the plugin register", interaction_type))
+    }
+  }
+}
+
+/// Internal function: public for testing. Sends the configure interaction request for the
+/// interaction type to the given plugin and converts the response.
+pub async fn configure_interaction_type_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  interaction_type: &CatalogueEntry,
+  content_type: Option<&ContentType>,
+  definition: HashMap<String, Value>
+) -> anyhow::Result<(Vec<InteractionContents>, Option<PluginConfiguration>)> {
+  let plugin_manifest = interaction_type_plugin(interaction_type)?;
+  debug!("Sending ConfigureInteraction request for interaction type '{}' to plugin {:?}",
+    interaction_type.key, plugin_manifest);
+  let request = ConfigureInteractionRequest {
+    content_type: content_type.map(|ct| ct.to_string()).unwrap_or_default(),
+    contents_config: Some(to_proto_struct(definition)),
+    interaction_type: interaction_type_name(interaction_type, plugin_manifest.name.as_str())
+  };
+
+  match plugin.configure_interaction(request).await {
+    Ok(response) => configure_interaction_result(response, plugin_manifest.name.as_str(),
+      plugin_manifest.version.as_str()),
+    Err(err) => {
+      error!("Call to plugin failed - {}", err);
+      Err(anyhow!("Call to plugin failed - {}", err))
+    }
+  }
+}
+
+fn interaction_type_plugin(interaction_type: &CatalogueEntry) -> anyhow::Result<&PactPluginManifest> {
+  interaction_type.plugin.as_ref()
+    .ok_or_else(|| anyhow!("Interaction type '{}' is provided by the core framework, not a plugin",
+      interaction_type.catalogue_key()))
+}
+
+/// Name of the interaction type. This is the key the plugin registered the interaction type with.
+fn interaction_type_name(interaction_type: &CatalogueEntry, plugin_name: &str) -> String {
+  interaction_type.key
+    .strip_prefix(format!("plugin/{}/interaction/", plugin_name).as_str())
+    .unwrap_or(interaction_type.key.as_str())
+    .to_string()
+}
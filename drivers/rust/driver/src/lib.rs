@@ -4,7 +4,20 @@ pub mod plugin_models;
 pub mod plugin_manager;
 mod child_process;
 pub mod proto;
+pub mod proto_json;
 pub mod catalogue_manager;
+pub mod callbacks;
+pub mod cancellation;
 pub mod content;
+pub mod diff;
+pub mod generators;
+pub mod interactions;
+pub mod markup;
+pub mod matchers;
+pub mod mock_server;
+pub mod pact_metadata;
+pub mod recording;
+pub mod telemetry;
 pub mod utils;
+pub mod verification;
 mod metrics;
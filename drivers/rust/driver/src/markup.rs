@@ -0,0 +1,139 @@
+//! Support for rendering the interaction markup returned by plugins (see `InteractionContents.interaction_markup`)
+//! for display in UIs. Plugins can return markup in CommonMark or HTML format. As the markup comes
+//! from the plugin (and ends up in the Pact file), it is always sanitised when rendered to HTML, so
+//! it is safe to display in a browser.
+
+use std::fmt::{self, Display, Formatter};
+
+use ammonia::Builder;
+use pact_models::v4::interaction::InteractionMarkup;
+use pulldown_cmark::{html, Options, Parser};
+
+use crate::content::InteractionContents;
+use crate::proto::interaction_response::MarkupType as ProtoMarkupType;
+
+/// Format of the interaction markup
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarkupType {
+  /// CommonMark (Markdown) format
+  #[default]
+  CommonMark,
+  /// HTML format
+  Html
+}
+
+impl MarkupType {
+  /// Parse the markup type as stored in the Pact file ("COMMON_MARK" or "HTML"). Any other value
+  /// is treated as CommonMark, which is the default.
+  pub fn parse(markup_type: &str) -> MarkupType {
+    if markup_type.eq_ignore_ascii_case("HTML") {
+      MarkupType::Html
+    } else {
+      MarkupType::CommonMark
+    }
+  }
+
+  pub(crate) fn from_proto(markup_type: ProtoMarkupType) -> MarkupType {
+    match markup_type {
+      ProtoMarkupType::CommonMark => MarkupType::CommonMark,
+      ProtoMarkupType::Html => MarkupType::Html
+    }
+  }
+}
+
+impl Display for MarkupType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      MarkupType::CommonMark => write!(f, "COMMON_MARK"),
+      MarkupType::Html => write!(f, "HTML")
+    }
+  }
+}
+
+/// Markup for an interaction or part of an interaction
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Markup {
+  /// Markup contents
+  pub markup: String,
+  /// Format of the markup
+  pub markup_type: MarkupType
+}
+
+impl Markup {
+  /// If there is no markup
+  pub fn is_empty(&self) -> bool {
+    self.markup.trim().is_empty()
+  }
+
+  /// Render the markup as sanitised HTML
+  pub fn to_html(&self) -> String {
+    match self.markup_type {
+      MarkupType::CommonMark => sanitise_html(commonmark_to_html(self.markup.as_str()).as_str()),
+      MarkupType::Html => sanitise_html(self.markup.as_str())
+    }
+  }
+}
+
+impl From<&InteractionContents> for Markup {
+  fn from(contents: &InteractionContents) -> Self {
+    Markup {
+      markup: contents.interaction_markup.clone(),
+      markup_type: contents.interaction_markup_type
+    }
+  }
+}
+
+impl From<&InteractionMarkup> for Markup {
+  fn from(markup: &InteractionMarkup) -> Self {
+    Markup {
+      markup: markup.markup.clone(),
+      markup_type: MarkupType::parse(markup.markup_type.as_str())
+    }
+  }
+}
+
+impl From<&Markup> for InteractionMarkup {
+  fn from(markup: &Markup) -> Self {
+    InteractionMarkup {
+      markup: markup.markup.clone(),
+      markup_type: markup.markup_type.to_string()
+    }
+  }
+}
+
+/// Convert CommonMark to HTML. Tables and strikethrough extensions are enabled. Note that the
+/// result is not sanitised, as CommonMark can contain raw HTML.
+pub fn commonmark_to_html(markup: &str) -> String {
+  let mut options = Options::empty();
+  options.insert(Options::ENABLE_TABLES);
+  options.insert(Options::ENABLE_STRIKETHROUGH);
+  let mut buffer = String::new();
+  html::push_html(&mut buffer, Parser::new_ext(markup, options));
+  buffer
+}
+
+/// Sanitise HTML so it is safe to display in a browser. Scripts, styles, event handler attributes
+/// and `javascript:` URLs are removed, and any unclosed tags are closed. The `class` attribute is
+/// kept so the markup can be styled.
+pub fn sanitise_html(markup: &str) -> String {
+  Builder::default()
+    .add_generic_attributes(&["class"])
+    .clean(markup)
+    .to_string()
+}
+
+/// Combine the markup from all the parts of an interaction into one sanitised HTML document,
+/// in the order given. Each part with markup is rendered in a `div` with the `interaction-part`
+/// class, and parts without markup are skipped.
+pub fn render_markup(parts: &[Markup]) -> String {
+  parts.iter()
+    .filter(|part| !part.is_empty())
+    .map(|part| format!("<div class=\"interaction-part\">\n{}</div>\n", part.to_html()))
+    .collect()
+}
+
+/// Combine the markup returned by a plugin for all the parts of an interaction into one sanitised
+/// HTML document (see `render_markup`).
+pub fn render_interaction_markup(parts: &[InteractionContents]) -> String {
+  render_markup(&parts.iter().map(Markup::from).collect::<Vec<_>>())
+}
@@ -0,0 +1,126 @@
+//! Support for matching rules provided by plugins
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use log::{debug, error};
+use serde_json::Value;
+
+use crate::cancellation::{CancellationToken, with_cancellation};
+use crate::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType, find_matcher};
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::{PactPluginManifest, PactPluginRpc, PluginInteractionConfig};
+use crate::proto::{MatchValueRequest, PluginConfiguration as ProtoPluginConfiguration};
+use crate::utils::{to_proto_struct, to_proto_value};
+
+/// Matching rule provided by a plugin (or the core framework)
+#[derive(Clone, Debug)]
+pub struct PluginMatcher {
+  /// Catalogue entry for this matcher
+  pub catalogue_entry: CatalogueEntry
+}
+
+impl PluginMatcher {
+  /// Plugin details for this matcher
+  pub fn plugin(&self) -> Option<PactPluginManifest> {
+    self.catalogue_entry.plugin.clone()
+  }
+
+  /// If this is a core framework matcher
+  pub fn is_core(&self) -> bool {
+    self.catalogue_entry.provider_type == CatalogueEntryProviderType::CORE
+  }
+
+  /// Plugin name that provides this matcher
+  pub fn plugin_name(&self) -> String {
+    self.catalogue_entry.plugin.as_ref()
+      .map(|p| p.name.clone())
+      .unwrap_or("core".to_string())
+  }
+
+  /// Plugin version that provides this matcher
+  pub fn plugin_version(&self) -> String {
+    self.catalogue_entry.plugin.as_ref()
+      .map(|p| p.version.clone())
+      .unwrap_or_default()
+  }
+
+  /// Name of the matching rule. This is the key the plugin registered the matcher with.
+  pub fn name(&self) -> String {
+    match &self.catalogue_entry.plugin {
+      Some(plugin) => self.catalogue_entry.key
+        .strip_prefix(format!("plugin/{}/matcher/", plugin.name).as_str())
+        .unwrap_or(self.catalogue_entry.key.as_str())
+        .to_string(),
+      None => self.catalogue_entry.key.clone()
+    }
+  }
+
+  /// Get the plugin to apply the matching rule to the actual value, given the expected value.
+  /// Returns the list of mismatches, which will be empty if the value matched. Errors are returned
+  /// if the plugin could not be called or failed to apply the rule. If the cancellation token is
+  /// cancelled before the plugin responds, the call to the plugin is aborted and an error returned.
+  pub async fn match_value(
+    &self,
+    expected: &Value,
+    actual: &Value,
+    rule_values: &HashMap<String, Value>,
+    path: &str,
+    plugin_config: Option<PluginInteractionConfig>,
+    cancellation_token: Option<&CancellationToken>
+  ) -> anyhow::Result<Vec<String>> {
+    let request = MatchValueRequest {
+      name: self.name(),
+      values: Some(to_proto_struct(rule_values.clone())),
+      expected: Some(to_proto_value(expected)),
+      actual: Some(to_proto_value(actual)),
+      path: path.to_string(),
+      plugin_configuration: plugin_config.map(|config| ProtoPluginConfiguration {
+        interaction_configuration: Some(to_proto_struct(config.interaction_configuration)),
+        pact_configuration: Some(to_proto_struct(config.pact_configuration))
+      })
+    };
+
+    let plugin_manifest = match &self.catalogue_entry.plugin {
+      Some(plugin) => plugin,
+      None => return Err(anyhow!("Matching rule '{}' is provided by the core framework, not a plugin", self.name()))
+    };
+    match lookup_plugin(&plugin_manifest.as_dependency()) {
+      Some(plugin) => {
+        debug!("Sending matchValue request to plugin {:?}", plugin_manifest);
+        let response = with_cancellation(cancellation_token, plugin.match_value(request)).await??;
+        if response.error.is_empty() {
+          Ok(response.mismatches)
+        } else {
+          Err(anyhow!("Plugin failed to apply matching rule '{}': {}", self.name(), response.error))
+        }
+      },
+      None => {
+        error!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry);
+        Err(anyhow!("Plugin for {:?} was not found in the plugin register", self.catalogue_entry))
+      }
+    }
+  }
+}
+
+/// Apply a matching rule that is not supported by the core framework. This will look up the
+/// matcher from the catalogue, and then get the plugin that provides it to apply it to the actual
+/// value. Returns the list of mismatches, which will be empty if the value matched.
+pub async fn match_with_plugin_rule(
+  rule_name: &str,
+  rule_values: &HashMap<String, Value>,
+  expected: &Value,
+  actual: &Value,
+  path: &str,
+  plugin_config: Option<PluginInteractionConfig>,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<Vec<String>> {
+  match find_matcher(rule_name) {
+    Some(matcher) => if matcher.is_core() {
+      Err(anyhow!("Matching rule '{}' is provided by the core framework, not a plugin", rule_name))
+    } else {
+      matcher.match_value(expected, actual, rule_values, path, plugin_config, cancellation_token).await
+    },
+    None => Err(anyhow!("No plugin provides a matcher for matching rule '{}'", rule_name))
+  }
+}
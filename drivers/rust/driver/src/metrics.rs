@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use crate::plugin_models::PactPluginManifest;
 
-static CIS: &'static [&str] = &[
+static CIS: &[&str] = &[
   "CI",
   "CONTINUOUS_INTEGRATION",
   "BSTRUSE_BUILD_DIR",
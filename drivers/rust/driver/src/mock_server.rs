@@ -0,0 +1,206 @@
+//! Support for mock servers provided by plugins (i.e. for protocols like gRPC)
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use log::{debug, error};
+use pact_models::PactSpecification;
+use pact_models::prelude::Pact;
+
+use crate::cancellation::{CancellationToken, with_cancellation};
+use crate::catalogue_manager::CatalogueEntry;
+use crate::content::ContentMismatch;
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::{CAPABILITY_START_MOCK_SERVER, PactPluginManifest, PactPluginRpc};
+use crate::proto::{
+  MockServerRequest,
+  MockServerResult as ProtoMockServerResult,
+  ShutdownMockServerRequest,
+  StartMockServerRequest
+};
+use crate::proto::start_mock_server_response;
+
+/// Configuration for the mock server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MockServerConfig {
+  /// Interface to bind to. Will default to the loopback adapter if not set
+  pub host_interface: Option<String>,
+  /// Port to bind to. A value of 0 will get the OS to open a random port
+  pub port: u32,
+  /// If TLS should be used (if supported by the mock server)
+  pub tls: bool
+}
+
+/// Details of a running mock server
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockServerDetails {
+  /// Unique key of the mock server, used to refer to it in later calls to the plugin
+  pub key: String,
+  /// Address the mock server is bound to
+  pub address: String,
+  /// Port the mock server is running on
+  pub port: u32,
+  /// Plugin that is running the mock server
+  pub plugin: PactPluginManifest
+}
+
+/// Result of a request (or message) received by the mock server
+#[derive(Clone, Debug)]
+pub struct MockServerResult {
+  /// Key of the interaction from the Pact file, if the request matched one
+  pub interaction_key: String,
+  /// Path or service/method that was received
+  pub path: String,
+  /// Error, if the request could not be processed or no interaction was found
+  pub error: String,
+  /// Any mismatches that occurred
+  pub mismatches: Vec<ContentMismatch>
+}
+
+impl From<&ProtoMockServerResult> for MockServerResult {
+  fn from(result: &ProtoMockServerResult) -> Self {
+    MockServerResult {
+      interaction_key: result.interaction_key.clone(),
+      path: result.path.clone(),
+      error: result.error.clone(),
+      mismatches: result.mismatches.iter().map(ContentMismatch::from).collect()
+    }
+  }
+}
+
+/// Start a mock server provided by a plugin (see `find_mock_server`) for the interactions in the
+/// Pact. The Pact is sent to the plugin in V4 format. The call is aborted if the cancellation token
+/// is cancelled.
+pub async fn start_mock_server(
+  catalogue_entry: &CatalogueEntry,
+  pact: &dyn Pact,
+  config: MockServerConfig,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<MockServerDetails> {
+  let plugin_manifest = entry_plugin(catalogue_entry)?;
+  match lookup_plugin(&plugin_manifest.as_dependency()) {
+    Some(plugin) => start_mock_server_with_plugin(&plugin, catalogue_entry, pact, config,
+      cancellation_token).await,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", catalogue_entry);
+      Err(anyhow!("Plugin for {:?} was not found in the plugin register", catalogue_entry))
+    }
+  }
+}
+
+fn entry_plugin(catalogue_entry: &CatalogueEntry) -> anyhow::Result<&PactPluginManifest> {
+  catalogue_entry.plugin.as_ref()
+    .ok_or_else(|| anyhow!("Mock server '{}' is not provided by a plugin", catalogue_entry.key))
+}
+
+/// Internal function: public for testing. Sends the start mock server request to the given plugin.
+pub async fn start_mock_server_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  catalogue_entry: &CatalogueEntry,
+  pact: &dyn Pact,
+  config: MockServerConfig,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<MockServerDetails> {
+  let plugin_manifest = entry_plugin(catalogue_entry)?.clone();
+  if !plugin.supports_for_entry(CAPABILITY_START_MOCK_SERVER, catalogue_entry) {
+    return Err(anyhow!("Plugin {}/{} does not support mock servers", plugin_manifest.name,
+      plugin_manifest.version));
+  }
+  let pact = pact.as_v4_pact()?.to_json(PactSpecification::V4)?;
+  let request = StartMockServerRequest {
+    host_interface: config.host_interface.unwrap_or_default(),
+    port: config.port,
+    tls: config.tls,
+    pact: pact.to_string()
+  };
+
+  debug!("Sending startMockServer request to plugin {:?}", plugin_manifest);
+  match with_cancellation(cancellation_token, plugin.start_mock_server(request)).await??.response {
+    Some(start_mock_server_response::Response::Details(details)) => Ok(MockServerDetails {
+      key: details.key,
+      address: details.address,
+      port: details.port,
+      plugin: plugin_manifest
+    }),
+    Some(start_mock_server_response::Response::Error(err)) =>
+      Err(anyhow!("Plugin {}/{} failed to start the mock server - {}", plugin_manifest.name,
+        plugin_manifest.version, err)),
+    None => Err(anyhow!("Plugin {}/{} did not return any mock server details", plugin_manifest.name,
+      plugin_manifest.version))
+  }
+}
+
+/// Shutdown a running mock server, returning the results of the interactions. If the plugin reports
+/// that the mock server did not shut down cleanly or not all the interactions matched, an error
+/// listing the failed results is returned. The call is aborted if the cancellation token is cancelled.
+pub async fn shutdown_mock_server(
+  mock_server: &MockServerDetails,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<Vec<MockServerResult>> {
+  match lookup_plugin(&mock_server.plugin.as_dependency()) {
+    Some(plugin) => with_cancellation(cancellation_token,
+      shutdown_mock_server_with_plugin(&plugin, mock_server)).await?,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", mock_server.plugin);
+      Err(anyhow!("Plugin {}/{} was not found in the plugin register", mock_server.plugin.name,
+        mock_server.plugin.version))
+    }
+  }
+}
+
+/// Internal function: public for testing. Sends the shutdown mock server request to the given plugin.
+pub async fn shutdown_mock_server_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  mock_server: &MockServerDetails
+) -> anyhow::Result<Vec<MockServerResult>> {
+  debug!("Sending shutdownMockServer request to plugin {:?}", mock_server.plugin);
+  let response = plugin.shutdown_mock_server(ShutdownMockServerRequest {
+    server_key: mock_server.key.clone()
+  }).await?;
+  let results = response.results.iter().map(MockServerResult::from).collect_vec();
+  if response.ok {
+    Ok(results)
+  } else if results.is_empty() {
+    Err(anyhow!("Plugin {}/{} failed to shut down mock server {}", mock_server.plugin.name,
+      mock_server.plugin.version, mock_server.key))
+  } else {
+    Err(anyhow!("Mock server {} did not match all the interactions - {}", mock_server.key,
+      results.iter().map(result_description).join("; ")))
+  }
+}
+
+fn result_description(result: &MockServerResult) -> String {
+  if result.error.is_empty() {
+    format!("{}: {}", result.path, result.mismatches.iter().map(|m| m.mismatch.as_str()).join(", "))
+  } else {
+    format!("{}: {}", result.path, result.error)
+  }
+}
+
+/// Get the matching results from a running mock server. An empty list means that all the
+/// interactions matched. The call is aborted if the cancellation token is cancelled.
+pub async fn get_mock_server_results(
+  mock_server: &MockServerDetails,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<Vec<MockServerResult>> {
+  match lookup_plugin(&mock_server.plugin.as_dependency()) {
+    Some(plugin) => with_cancellation(cancellation_token,
+      get_mock_server_results_with_plugin(&plugin, mock_server)).await?,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", mock_server.plugin);
+      Err(anyhow!("Plugin {}/{} was not found in the plugin register", mock_server.plugin.name,
+        mock_server.plugin.version))
+    }
+  }
+}
+
+/// Internal function: public for testing. Sends the get mock server results request to the given plugin.
+pub async fn get_mock_server_results_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  mock_server: &MockServerDetails
+) -> anyhow::Result<Vec<MockServerResult>> {
+  debug!("Sending getMockServerResults request to plugin {:?}", mock_server.plugin);
+  let response = plugin.get_mock_server_results(MockServerRequest {
+    server_key: mock_server.key.clone()
+  }).await?;
+  Ok(response.results.iter().map(MockServerResult::from).collect())
+}
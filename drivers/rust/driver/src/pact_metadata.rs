@@ -0,0 +1,194 @@
+//! Support for persisting the plugin configuration in the Pact file metadata. Plugins return
+//! Pact level configuration when interactions are configured (`PluginConfiguration.pact_configuration`),
+//! which is stored under the `plugins` attribute of the Pact metadata, one entry per plugin name
+//! and version. This is then read back when verifying the Pact and passed to the plugin with the
+//! interaction level configuration.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use log::debug;
+use serde_json::{json, Map, Value};
+
+use crate::catalogue_manager::compare_versions;
+use crate::content::PluginConfiguration;
+use crate::plugin_models::{PactPluginManifest, PluginInteractionConfig};
+
+/// Value from the plugin configuration that conflicts with the value already stored in the
+/// Pact metadata
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigurationConflict {
+  /// Plugin name
+  pub name: String,
+  /// Plugin version
+  pub version: String,
+  /// Path to the conflicting value in the plugin configuration
+  pub path: String,
+  /// Value in the Pact metadata
+  pub existing: Value,
+  /// Value from the plugin configuration
+  pub new: Value
+}
+
+impl Display for ConfigurationConflict {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Plugin {}/{} configuration value at '{}' is {} in the Pact metadata, but was {}",
+      self.name, self.version, self.path, self.existing, self.new)
+  }
+}
+
+/// Reasons that merging plugin configuration into the Pact metadata can fail
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataMergeError {
+  /// The Pact metadata is not in the expected format
+  InvalidMetadata(String),
+  /// Values from the plugin configuration conflict with values already in the Pact metadata
+  Conflicts(Vec<ConfigurationConflict>)
+}
+
+impl Display for MetadataMergeError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      MetadataMergeError::InvalidMetadata(err) => write!(f, "Pact metadata is not valid - {}", err),
+      MetadataMergeError::Conflicts(conflicts) => write!(f, "Plugin configuration conflicts with the Pact metadata: {}",
+        conflicts.iter().map(|conflict| conflict.to_string()).collect::<Vec<_>>().join(", "))
+    }
+  }
+}
+
+impl std::error::Error for MetadataMergeError {}
+
+/// Merges the Pact level configuration from the plugin into the Pact metadata, returning the
+/// updated metadata. The configuration is stored in the entry for the plugin name and version
+/// under `plugins`, which is sorted by name and version so the result does not depend on the
+/// order plugins were called in. Objects are merged recursively. Any other value that is
+/// already in the metadata must be equal to the new value, otherwise all the conflicting values
+/// are returned as an error and the metadata is not updated.
+pub fn merge_plugin_configuration(
+  metadata: &Value,
+  plugin: &PactPluginManifest,
+  configuration: &PluginConfiguration
+) -> Result<Value, MetadataMergeError> {
+  let mut metadata = match metadata {
+    Value::Object(map) => map.clone(),
+    Value::Null => Map::new(),
+    _ => return Err(MetadataMergeError::InvalidMetadata(format!("Expected a JSON object, got '{}'", metadata)))
+  };
+  let mut plugins = match metadata.remove("plugins") {
+    Some(Value::Array(plugins)) => plugins,
+    Some(Value::Null) | None => vec![],
+    Some(plugins) => return Err(MetadataMergeError::InvalidMetadata(
+      format!("Expected 'plugins' to be a JSON array, got '{}'", plugins)))
+  };
+
+  let index = plugins.iter().position(|entry| is_entry_for(entry, plugin.name.as_str(), plugin.version.as_str()));
+  let mut plugin_configuration = match index.map(|index| &plugins[index]["configuration"]) {
+    Some(Value::Object(config)) => config.clone(),
+    Some(Value::Null) | None => Map::new(),
+    Some(config) => return Err(MetadataMergeError::InvalidMetadata(
+      format!("Expected the configuration for plugin {}/{} to be a JSON object, got '{}'", plugin.name,
+        plugin.version, config)))
+  };
+
+  let mut conflicts = vec![];
+  for (key, value) in &configuration.pact_configuration {
+    merge_value(&mut plugin_configuration, key, value, format!("$.{}", key).as_str(), plugin, &mut conflicts);
+  }
+  if !conflicts.is_empty() {
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+    return Err(MetadataMergeError::Conflicts(conflicts));
+  }
+
+  let entry = json!({
+    "name": plugin.name,
+    "version": plugin.version,
+    "configuration": plugin_configuration
+  });
+  match index {
+    Some(index) => plugins[index] = entry,
+    None => plugins.push(entry)
+  }
+  plugins.sort_by(|a, b| {
+    a["name"].as_str().cmp(&b["name"].as_str())
+      .then_with(|| compare_entry_versions(a, b))
+  });
+  debug!("Merged configuration for plugin {}/{} into the Pact metadata", plugin.name, plugin.version);
+
+  metadata.insert("plugins".to_string(), Value::Array(plugins));
+  Ok(Value::Object(metadata))
+}
+
+fn merge_value(
+  target: &mut Map<String, Value>,
+  key: &str,
+  value: &Value,
+  path: &str,
+  plugin: &PactPluginManifest,
+  conflicts: &mut Vec<ConfigurationConflict>
+) {
+  match (target.get_mut(key), value) {
+    (None, _) => {
+      target.insert(key.to_string(), value.clone());
+    }
+    (Some(Value::Object(existing)), Value::Object(value)) => {
+      for (key, value) in value {
+        merge_value(existing, key, value, format!("{}.{}", path, key).as_str(), plugin, conflicts);
+      }
+    }
+    (Some(existing), _) => if existing != value {
+      conflicts.push(ConfigurationConflict {
+        name: plugin.name.clone(),
+        version: plugin.version.clone(),
+        path: path.to_string(),
+        existing: existing.clone(),
+        new: value.clone()
+      });
+    }
+  }
+}
+
+fn compare_entry_versions(a: &Value, b: &Value) -> Ordering {
+  compare_versions(a["version"].as_str().unwrap_or_default(), b["version"].as_str().unwrap_or_default())
+}
+
+fn is_entry_for(entry: &Value, name: &str, version: &str) -> bool {
+  entry["name"].as_str() == Some(name) && entry["version"].as_str() == Some(version)
+}
+
+/// Reads the Pact level configuration for the plugin back from the Pact metadata and combines it
+/// with the interaction level configuration (keyed by plugin name, as stored on V4 interactions),
+/// to pass to the plugin when verifying the interaction. If there is no entry for the plugin
+/// version, the entry for the latest version of the plugin in the metadata is used. Returns `None`
+/// if there is no configuration for the plugin.
+pub fn plugin_interaction_config(
+  metadata: &Value,
+  plugin: &PactPluginManifest,
+  interaction_configuration: &HashMap<String, HashMap<String, Value>>
+) -> Option<PluginInteractionConfig> {
+  let entries = match &metadata["plugins"] {
+    Value::Array(plugins) => plugins.iter()
+      .filter(|entry| entry["name"].as_str() == Some(plugin.name.as_str()))
+      .collect(),
+    _ => vec![]
+  };
+  let entry = entries.iter()
+    .find(|entry| is_entry_for(entry, plugin.name.as_str(), plugin.version.as_str()))
+    .or_else(|| entries.iter().max_by(|a, b| compare_entry_versions(a, b)));
+  let pact_configuration: HashMap<String, Value> = match entry.map(|entry| &entry["configuration"]) {
+    Some(Value::Object(config)) => config.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+    _ => HashMap::default()
+  };
+  let interaction_configuration = interaction_configuration.get(plugin.name.as_str())
+    .cloned()
+    .unwrap_or_default();
+
+  if pact_configuration.is_empty() && interaction_configuration.is_empty() {
+    None
+  } else {
+    Some(PluginInteractionConfig {
+      pact_configuration,
+      interaction_configuration
+    })
+  }
+}
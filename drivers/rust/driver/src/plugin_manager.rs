@@ -12,45 +12,63 @@ use std::thread;
 
 use anyhow::anyhow;
 use lazy_static::lazy_static;
-use log::{debug, max_level, trace, warn};
+use log::{debug, error, max_level, trace, warn};
+use tracing::instrument;
 use os_info::Type;
 use sysinfo::{Pid, ProcessExt, RefreshKind, Signal, System, SystemExt};
 use tokio::process::Command;
 
-use crate::catalogue_manager::{register_plugin_entries, remove_plugin_entries};
+use crate::catalogue_manager::{compare_versions, register_plugin_entries, remove_plugin_entries};
+use crate::callbacks::callback_service;
 use crate::child_process::ChildPluginProcess;
 use crate::metrics::send_metrics;
-use crate::plugin_models::{PactPlugin, PactPluginManifest, PactPluginRpc, PluginDependency};
-use crate::proto::InitPluginRequest;
+use crate::plugin_models::{
+  CAPABILITY_PLUGIN_HOST,
+  PactPlugin,
+  PactPluginManifest,
+  PactPluginRpc,
+  PluginDependency,
+  SUPPORTED_INTERFACE_VERSIONS
+};
+use crate::proto::{InitPluginRequest, InitPluginResponse};
 
 lazy_static! {
   static ref PLUGIN_MANIFEST_REGISTER: Mutex<HashMap<String, PactPluginManifest>> = Mutex::new(HashMap::new());
   static ref PLUGIN_REGISTER: Mutex<HashMap<String, PactPlugin>> = Mutex::new(HashMap::new());
+  // Plugins are loaded one at a time so that concurrent loads don't start the same plugin twice
+  static ref PLUGIN_LOAD_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
 }
 
 /// Load the plugin defined by the dependency information. Will first look in the global
 /// plugin registry.
+#[instrument(skip_all, fields(plugin = %plugin.name, version = ?plugin.version))]
 pub async fn load_plugin(plugin: &PluginDependency) -> anyhow::Result<PactPlugin> {
   let thread_id = thread::current().id();
   debug!("Loading plugin {:?}", plugin);
   trace!("Rust plugin driver version {}", option_env!("CARGO_PKG_VERSION").unwrap_or_default());
-  trace!("load_plugin {:?}: Waiting on PLUGIN_REGISTER lock", thread_id);
-  let mut inner = PLUGIN_REGISTER.lock().unwrap();
-  trace!("load_plugin {:?}: Got PLUGIN_REGISTER lock", thread_id);
-  let result = match lookup_plugin_inner(plugin, &mut inner) {
+  trace!("load_plugin {:?}: Waiting on PLUGIN_LOAD_LOCK lock", thread_id);
+  let _load_guard = PLUGIN_LOAD_LOCK.lock().await;
+  trace!("load_plugin {:?}: Got PLUGIN_LOAD_LOCK lock", thread_id);
+  let running_plugin = {
+    let mut inner = PLUGIN_REGISTER.lock().unwrap();
+    lookup_plugin_inner(plugin, &mut inner).map(|plugin| {
+      plugin.update_access();
+      plugin.clone()
+    })
+  };
+  let result = match running_plugin {
     Some(plugin) => {
       debug!("Found running plugin {:?}", plugin);
-      plugin.update_access();
-      Ok(plugin.clone())
+      Ok(plugin)
     },
     None => {
       debug!("Did not find plugin, will start it");
       let manifest = load_plugin_manifest(plugin)?;
       send_metrics(&manifest);
-      initialise_plugin(&manifest, &mut inner).await
+      initialise_plugin(&manifest).await
     }
   };
-  trace!("load_plugin {:?}: Releasing PLUGIN_REGISTER lock", thread_id);
+  trace!("load_plugin {:?}: Releasing PLUGIN_LOAD_LOCK lock", thread_id);
   result
 }
 
@@ -63,7 +81,7 @@ fn lookup_plugin_inner<'a>(
   } else {
     plugin_register.iter_mut()
       .filter(|(_, value)| value.manifest.name == plugin.name)
-      .max_by(|(_, v1), (_, v2)| v1.manifest.version.cmp(&v2.manifest.version))
+      .max_by(|(_, v1), (_, v2)| compare_versions(&v1.manifest.version, &v2.manifest.version))
       .map(|(_, plugin)| plugin)
   }
 }
@@ -150,27 +168,26 @@ pub fn lookup_plugin_manifest(plugin: &PluginDependency) -> Option<PactPluginMan
   } else {
     guard.iter()
       .filter(|(_, value)| value.name == plugin.name)
-      .max_by(|(_, v1), (_, v2)| v1.version.cmp(&v2.version))
+      .max_by(|(_, v1), (_, v2)| compare_versions(&v1.version, &v2.version))
       .map(|(_, p)| p.clone())
   }
 }
 
-async fn initialise_plugin(
-  manifest: &PactPluginManifest,
-  plugin_register: &mut HashMap<String, PactPlugin>
-) -> anyhow::Result<PactPlugin> {
+async fn initialise_plugin(manifest: &PactPluginManifest) -> anyhow::Result<PactPlugin> {
   match manifest.executable_type.as_str() {
     "exec" => {
-      let plugin = start_plugin_process(manifest).await?;
+      check_interface_version(manifest, &[])?;
+      let mut plugin = start_plugin_process(manifest).await?;
       debug!("Plugin process started OK (port = {}), sending init message", plugin.port());
 
-      init_handshake(manifest, &plugin).await.map_err(|err| {
+      let response = init_handshake(manifest, &plugin).await.map_err(|err| {
         plugin.kill();
         anyhow!("Failed to send init request to the plugin - {}", err)
       })?;
+      plugin.set_capabilities(&response);
 
       let key = format!("{}/{}", manifest.name, manifest.version);
-      plugin_register.insert(key, plugin.clone());
+      PLUGIN_REGISTER.lock().unwrap().insert(key, plugin.clone());
 
       Ok(plugin)
     }
@@ -178,17 +195,57 @@ async fn initialise_plugin(
   }
 }
 
-/// Internal function: public for testing
-pub async fn init_handshake(manifest: &PactPluginManifest, plugin: &dyn PactPluginRpc) -> anyhow::Result<()> {
+/// Internal function: public for testing. Sends the init request to the plugin and registers the
+/// catalogue entries it returns. Plugins that do not support any of the plugin interface versions
+/// supported by this driver are rejected.
+#[instrument(skip_all, fields(plugin = %manifest.name, version = %manifest.version))]
+pub async fn init_handshake(
+  manifest: &PactPluginManifest,
+  plugin: &dyn PactPluginRpc
+) -> anyhow::Result<InitPluginResponse> {
+  let callback_service = callback_service()
+    .unwrap_or_else(|err| {
+      warn!("Failed to start the plugin callback service, plugins will not be able to call back - {}", err);
+      None
+    });
   let request = InitPluginRequest {
     implementation: "plugin-driver-rust".to_string(),
-    version: option_env!("CARGO_PKG_VERSION").unwrap_or("0").to_string()
+    version: option_env!("CARGO_PKG_VERSION").unwrap_or("0").to_string(),
+    capabilities: if callback_service.is_some() {
+      vec![CAPABILITY_PLUGIN_HOST.to_string()]
+    } else {
+      vec![]
+    },
+    callback_address: callback_service.as_ref()
+      .map(|service| service.address.to_string())
+      .unwrap_or_default(),
+    callback_token: callback_service.map(|service| service.token).unwrap_or_default(),
+    interface_versions: SUPPORTED_INTERFACE_VERSIONS.to_vec()
   };
   let response = plugin.init_plugin(request).await?;
   debug!("Got init response {:?} from plugin {}", response, manifest.name);
+  check_interface_version(manifest, &response.interface_versions)?;
   register_plugin_entries(manifest, &response.catalogue);
   tokio::task::spawn(async { publish_updated_catalogue() });
-  Ok(())
+  Ok(response)
+}
+
+/// Checks that the plugin supports one of the interface versions supported by this driver. The
+/// versions the plugin reported in the init handshake take precedence over the manifest.
+fn check_interface_version(manifest: &PactPluginManifest, interface_versions: &[u32]) -> anyhow::Result<()> {
+  let plugin_versions = if interface_versions.is_empty() {
+    vec![manifest.plugin_interface_version as u32]
+  } else {
+    interface_versions.to_vec()
+  };
+  if plugin_versions.iter().any(|version| SUPPORTED_INTERFACE_VERSIONS.contains(version)) {
+    Ok(())
+  } else {
+    error!("Plugin {}/{} supports plugin interface versions {:?}, but this driver only supports {:?}",
+      manifest.name, manifest.version, plugin_versions, SUPPORTED_INTERFACE_VERSIONS);
+    Err(anyhow!("Plugin {}/{} supports plugin interface versions {:?}, but this driver only supports {:?}",
+      manifest.name, manifest.version, plugin_versions, SUPPORTED_INTERFACE_VERSIONS))
+  }
 }
 
 async fn start_plugin_process(manifest: &PactPluginManifest) -> anyhow::Result<PactPlugin> {
@@ -244,7 +301,7 @@ pub fn shutdown_plugins() {
   for plugin in guard.values() {
     debug!("Shutting down plugin {:?}", plugin);
     plugin.kill();
-    remove_plugin_entries(&plugin.manifest.name);
+    remove_plugin_entries(&plugin.manifest.name, &plugin.manifest.version);
   }
   guard.clear();
   trace!("shutdown_plugins {:?}: Releasing PLUGIN_REGISTER lock", thread_id);
@@ -254,7 +311,7 @@ pub fn shutdown_plugins() {
 pub fn shutdown_plugin(plugin: &mut PactPlugin) {
   debug!("Shutting down plugin {}:{}", plugin.manifest.name, plugin.manifest.version);
   plugin.kill();
-  remove_plugin_entries(&plugin.manifest.name);
+  remove_plugin_entries(&plugin.manifest.name, &plugin.manifest.version);
 }
 
 // TODO
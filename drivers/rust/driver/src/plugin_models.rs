@@ -3,21 +3,27 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
 use log::trace;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::{debug_span, Instrument, Span};
 
+use crate::catalogue_manager::CatalogueEntry;
 use crate::child_process::ChildPluginProcess;
 use crate::proto::*;
 use crate::proto::pact_plugin_client::PactPluginClient;
+use crate::recording::record_call;
+use crate::telemetry::traced_request;
 
 /// Type of plugin dependencies
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Hash, Default)]
 pub enum PluginDependencyType {
   /// Required operating system package
   OSPackage,
   /// Dependency on another plugin
+  #[default]
   Plugin,
   /// Dependency on a shared library
   Library,
@@ -25,12 +31,6 @@ pub enum PluginDependencyType {
   Executable
 }
 
-impl Default for PluginDependencyType {
-  fn default() -> Self {
-    PluginDependencyType::Plugin
-  }
-}
-
 /// Plugin dependency
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Hash)]
 #[serde(rename_all = "camelCase")]
@@ -109,20 +109,106 @@ impl Default for PactPluginManifest {
   }
 }
 
-/// Trait with remote-calling methods for a running plugin
+/// Versions of the plugin interface supported by this driver
+pub const SUPPORTED_INTERFACE_VERSIONS: [u32; 1] = [1];
+
+/// Capability for plugins that implement the `CompareContentsBatch` RPC
+pub const CAPABILITY_COMPARE_CONTENTS_BATCH: &str = "CompareContentsBatch";
+/// Capability for plugins that implement the `CompareStream` RPC
+pub const CAPABILITY_COMPARE_STREAM: &str = "CompareStream";
+/// Capability for plugins that implement the mock server RPCs
+pub const CAPABILITY_START_MOCK_SERVER: &str = "StartMockServer";
+/// Capability for plugins that implement the provider verification RPCs
+pub const CAPABILITY_VERIFY_INTERACTION: &str = "VerifyInteraction";
+/// Capability the driver sends to plugins if it provides the `PactPluginHost` callback service
+pub const CAPABILITY_PLUGIN_HOST: &str = "PactPluginHost";
+
+/// Trait with remote-calling methods for a running plugin. The RPC methods added after the first
+/// version of the plugin interface have default implementations that return an error, so
+/// implementations only need to provide the ones they support.
 #[async_trait]
-pub trait PactPluginRpc {
+pub trait PactPluginRpc: Sync {
+  /// Optional capabilities (RPC methods) the plugin reported in the init handshake. Returns `None`
+  /// if the plugin did not report any (i.e. it was written before capabilities were added).
+  fn capabilities(&self) -> Option<Vec<String>> {
+    None
+  }
+
+  /// If the plugin implements the optional capability (see `capabilities`). Plugins that did not
+  /// report any capabilities are assumed to not implement any of the optional methods.
+  fn supports(&self, capability: &str) -> bool {
+    match self.capabilities() {
+      Some(capabilities) => capabilities.iter().any(|c| c == capability),
+      None => false
+    }
+  }
+
+  /// If the plugin implements the optional capability for the catalogue entry. For plugins that
+  /// did not report any capabilities, this falls back to the capabilities the catalogue entry
+  /// declares (see `CatalogueEntry::declares_capability`).
+  fn supports_for_entry(&self, capability: &str, entry: &CatalogueEntry) -> bool {
+    match self.capabilities() {
+      Some(capabilities) => capabilities.iter().any(|c| c == capability),
+      None => entry.declares_capability(capability)
+    }
+  }
+
   /// Send an init request to the plugin process
   async fn init_plugin(&self, request: InitPluginRequest) -> anyhow::Result<InitPluginResponse>;
 
   /// Send a compare contents request to the plugin process
   async fn compare_contents(&self, request: CompareContentsRequest) -> anyhow::Result<CompareContentsResponse>;
 
+  /// Send a request to the plugin process to compare a number of contents in one call
+  async fn compare_contents_batch(&self, _request: CompareContentsBatchRequest) -> anyhow::Result<CompareContentsBatchResponse> {
+    Err(anyhow!("The CompareContentsBatch RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to compare a stream of messages against the expected sequence
+  async fn compare_stream(&self, _request: CompareStreamRequest) -> anyhow::Result<CompareStreamResponse> {
+    Err(anyhow!("The CompareStream RPC is not supported by this plugin"))
+  }
+
   /// Send a configure contents request to the plugin process
   async fn configure_interaction(&self, request: ConfigureInteractionRequest) -> anyhow::Result<ConfigureInteractionResponse>;
 
   /// Send a generate content request to the plugin
   async fn generate_content(&self, request: GenerateContentRequest) -> anyhow::Result<GenerateContentResponse>;
+
+  /// Send a request to the plugin to apply one of its matching rules to a value
+  async fn match_value(&self, _request: MatchValueRequest) -> anyhow::Result<MatchValueResponse> {
+    Err(anyhow!("The MatchValue RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to generate a value using one of its generators
+  async fn generate_value(&self, _request: GenerateValueRequest) -> anyhow::Result<GenerateValueResponse> {
+    Err(anyhow!("The GenerateValue RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to start a mock server
+  async fn start_mock_server(&self, _request: StartMockServerRequest) -> anyhow::Result<StartMockServerResponse> {
+    Err(anyhow!("The StartMockServer RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to shutdown a running mock server
+  async fn shutdown_mock_server(&self, _request: ShutdownMockServerRequest) -> anyhow::Result<ShutdownMockServerResponse> {
+    Err(anyhow!("The ShutdownMockServer RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to get the matching results from a running mock server
+  async fn get_mock_server_results(&self, _request: MockServerRequest) -> anyhow::Result<MockServerResults> {
+    Err(anyhow!("The GetMockServerResults RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to prepare an interaction for verification
+  async fn prepare_interaction_for_verification(&self, _request: VerificationPreparationRequest) -> anyhow::Result<VerificationPreparationResponse> {
+    Err(anyhow!("The PrepareInteractionForVerification RPC is not supported by this plugin"))
+  }
+
+  /// Send a request to the plugin to verify an interaction against the provider
+  async fn verify_interaction(&self, _request: VerifyInteractionRequest) -> anyhow::Result<VerifyInteractionResponse> {
+    Err(anyhow!("The VerifyInteraction RPC is not supported by this plugin"))
+  }
 }
 
 /// Running plugin details
@@ -134,45 +220,167 @@ pub struct PactPlugin {
   /// Running child process
   pub child: Arc<ChildPluginProcess>,
 
+  /// Optional capabilities the plugin reported in the init handshake, or `None` if it did not
+  /// report any
+  pub capabilities: Option<Vec<String>>,
+
+  /// Versions of the plugin interface the plugin reported in the init handshake
+  pub interface_versions: Vec<u32>,
+
   /// Count of access to the plugin. If this is ever zero, the plugin process will be shutdown
   access_count: usize
 }
 
 #[async_trait]
 impl PactPluginRpc for PactPlugin {
+  fn capabilities(&self) -> Option<Vec<String>> {
+    self.capabilities.clone()
+  }
+
   /// Send an init request to the plugin process
   async fn init_plugin(&self, request: InitPluginRequest) -> anyhow::Result<InitPluginResponse> {
-    let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
-    let response = client.init_plugin(tonic::Request::new(request)).await?;
-    Ok(response.get_ref().clone())
+    record_call(&self.manifest, "InitPlugin", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.init_plugin(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("InitPlugin"))).await
   }
 
   /// Send a compare contents request to the plugin process
   async fn compare_contents(&self, request: CompareContentsRequest) -> anyhow::Result<CompareContentsResponse> {
-    let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
-    let response = client.compare_contents(tonic::Request::new(request)).await?;
-    Ok(response.get_ref().clone())
+    record_call(&self.manifest, "CompareContents", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.compare_contents(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("CompareContents"))).await
+  }
+
+  /// Send a request to the plugin process to compare a number of contents in one call
+  async fn compare_contents_batch(&self, request: CompareContentsBatchRequest) -> anyhow::Result<CompareContentsBatchResponse> {
+    record_call(&self.manifest, "CompareContentsBatch", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.compare_contents_batch(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("CompareContentsBatch"))).await
+  }
+
+  /// Send a request to the plugin to compare a stream of messages against the expected sequence
+  async fn compare_stream(&self, request: CompareStreamRequest) -> anyhow::Result<CompareStreamResponse> {
+    record_call(&self.manifest, "CompareStream", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.compare_stream(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("CompareStream"))).await
   }
 
   /// Send a configure contents request to the plugin process
   async fn configure_interaction(&self, request: ConfigureInteractionRequest) -> anyhow::Result<ConfigureInteractionResponse> {
-    let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
-    let response = client.configure_interaction(tonic::Request::new(request)).await?;
-    Ok(response.get_ref().clone())
+    record_call(&self.manifest, "ConfigureInteraction", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.configure_interaction(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("ConfigureInteraction"))).await
   }
 
   /// Send a generate content request to the plugin
   async fn generate_content(&self, request: GenerateContentRequest) -> anyhow::Result<GenerateContentResponse> {
-    let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
-    let response = client.generate_content(tonic::Request::new(request)).await?;
-    Ok(response.get_ref().clone())
+    record_call(&self.manifest, "GenerateContent", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.generate_content(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("GenerateContent"))).await
+  }
+
+  /// Send a request to the plugin to apply one of its matching rules to a value
+  async fn match_value(&self, request: MatchValueRequest) -> anyhow::Result<MatchValueResponse> {
+    record_call(&self.manifest, "MatchValue", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.match_value(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("MatchValue"))).await
+  }
+
+  /// Send a request to the plugin to generate a value using one of its generators
+  async fn generate_value(&self, request: GenerateValueRequest) -> anyhow::Result<GenerateValueResponse> {
+    record_call(&self.manifest, "GenerateValue", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.generate_value(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("GenerateValue"))).await
+  }
+
+  /// Send a request to the plugin to start a mock server
+  async fn start_mock_server(&self, request: StartMockServerRequest) -> anyhow::Result<StartMockServerResponse> {
+    record_call(&self.manifest, "StartMockServer", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.start_mock_server(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("StartMockServer"))).await
+  }
+
+  /// Send a request to the plugin to shutdown a running mock server
+  async fn shutdown_mock_server(&self, request: ShutdownMockServerRequest) -> anyhow::Result<ShutdownMockServerResponse> {
+    record_call(&self.manifest, "ShutdownMockServer", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.shutdown_mock_server(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("ShutdownMockServer"))).await
+  }
+
+  /// Send a request to the plugin to get the matching results from a running mock server
+  async fn get_mock_server_results(&self, request: MockServerRequest) -> anyhow::Result<MockServerResults> {
+    record_call(&self.manifest, "GetMockServerResults", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.get_mock_server_results(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("GetMockServerResults"))).await
+  }
+
+  /// Send a request to the plugin to prepare an interaction for verification
+  async fn prepare_interaction_for_verification(&self, request: VerificationPreparationRequest) -> anyhow::Result<VerificationPreparationResponse> {
+    record_call(&self.manifest, "PrepareInteractionForVerification", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.prepare_interaction_for_verification(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("PrepareInteractionForVerification"))).await
+  }
+
+  /// Send a request to the plugin to verify an interaction against the provider
+  async fn verify_interaction(&self, request: VerifyInteractionRequest) -> anyhow::Result<VerifyInteractionResponse> {
+    record_call(&self.manifest, "VerifyInteraction", request, |request| async move {
+      let mut client = PactPluginClient::connect(format!("http://127.0.0.1:{}", self.child.port())).await?;
+      let response = client.verify_interaction(traced_request(request)).await?;
+      Ok(response.get_ref().clone())
+    }.instrument(self.call_span("VerifyInteraction"))).await
   }
 }
 
 impl PactPlugin {
   /// Create a new Plugin
   pub fn new(manifest: &PactPluginManifest, child: ChildPluginProcess) -> Self {
-    PactPlugin { manifest: manifest.clone(), child: Arc::new(child), access_count: 1 }
+    PactPlugin {
+      manifest: manifest.clone(),
+      child: Arc::new(child),
+      capabilities: None,
+      interface_versions: vec![],
+      access_count: 1
+    }
+  }
+
+  /// Store the capabilities and interface versions the plugin reported in the init handshake
+  pub fn set_capabilities(&mut self, response: &InitPluginResponse) {
+    self.capabilities = if response.capabilities.is_empty() {
+      None
+    } else {
+      Some(response.capabilities.clone())
+    };
+    self.interface_versions = response.interface_versions.clone();
+  }
+
+  /// Span for a call to the RPC method of the plugin. The trace context of the span is sent to the
+  /// plugin with the request.
+  fn call_span(&self, method: &str) -> Span {
+    debug_span!("plugin_call", plugin = %self.manifest.name, version = %self.manifest.version, method)
   }
 
   /// Port the plugin is running on
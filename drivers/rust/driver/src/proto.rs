@@ -1 +1,6 @@
+#![allow(clippy::derivable_impls, clippy::redundant_closure)]
+
 tonic::include_proto!("io.pact.plugin");
+
+/// Encoded file descriptor set for the plugin proto file (including the imported well known types)
+pub(crate) const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/plugin_descriptor.bin"));
@@ -0,0 +1,410 @@
+//! Conversion of the plugin messages to and from the Protobuf JSON format (see
+//! https://developers.google.com/protocol-buffers/docs/proto3#json). The conversion is driven by
+//! the descriptors of the plugin proto file, so it follows any changes to the proto file. Only the
+//! field types that the plugin proto file uses are supported.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, Context};
+use bytes::Buf;
+use lazy_static::lazy_static;
+use prost::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use prost::Message;
+use prost_types::{DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+use prost_types::field_descriptor_proto::{Label, Type};
+use serde_json::{Map, Value};
+
+use crate::proto::FILE_DESCRIPTOR_SET;
+use crate::utils::{proto_struct_to_json, proto_value_to_json, to_proto_struct, to_proto_value};
+
+lazy_static! {
+  static ref DESCRIPTORS: Descriptors = Descriptors::load(FILE_DESCRIPTOR_SET)
+    .expect("The descriptors for the plugin proto file are not valid");
+}
+
+/// Converts the message to the Protobuf JSON format. The type name is the fully qualified name of
+/// the message type (i.e. `io.pact.plugin.CompareContentsRequest`).
+pub fn message_to_json<M: Message>(type_name: &str, message: &M) -> anyhow::Result<Value> {
+  DESCRIPTORS.decode_message(type_name, message.encode_to_vec().as_slice())
+    .with_context(|| format!("Could not convert {} to JSON", type_name))
+}
+
+/// Converts JSON in the Protobuf JSON format to a message. The type name is the fully qualified
+/// name of the message type (i.e. `io.pact.plugin.CompareContentsRequest`).
+pub fn message_from_json<M: Message + Default>(type_name: &str, json: &Value) -> anyhow::Result<M> {
+  let mut buffer = vec![];
+  DESCRIPTORS.encode_message(type_name, json, &mut buffer)
+    .and_then(|_| M::decode(buffer.as_slice()).map_err(|err| anyhow!(err)))
+    .with_context(|| format!("Could not convert JSON to {}", type_name))
+}
+
+/// Fully qualified names of the input and output message types of a service method. The method
+/// name is also fully qualified (i.e. `io.pact.plugin.PactPlugin.CompareContents`).
+pub fn method_types(method: &str) -> Option<(&'static str, &'static str)> {
+  DESCRIPTORS.methods.get(method)
+    .map(|(input, output)| (input.as_str(), output.as_str()))
+}
+
+/// Message and enum types and service methods from the file descriptors, keyed by their fully
+/// qualified names
+#[derive(Default)]
+struct Descriptors {
+  messages: HashMap<String, DescriptorProto>,
+  enums: HashMap<String, EnumDescriptorProto>,
+  methods: HashMap<String, (String, String)>
+}
+
+/// Wrapper type used for optional byte fields. It is represented by the JSON for the wrapped value.
+const BYTES_VALUE: &str = "google.protobuf.BytesValue";
+
+/// Field value as read from the wire
+#[derive(Clone, Copy)]
+enum WireValue<'a> {
+  Varint(u64),
+  LengthDelimited(&'a [u8])
+}
+
+impl Descriptors {
+  fn load(bytes: &[u8]) -> anyhow::Result<Self> {
+    let descriptor_set = FileDescriptorSet::decode(bytes)?;
+    let mut descriptors = Descriptors::default();
+    for file in &descriptor_set.file {
+      let prefix = if file.package().is_empty() {
+        String::default()
+      } else {
+        format!("{}.", file.package())
+      };
+      for message in &file.message_type {
+        descriptors.add_message(prefix.as_str(), message);
+      }
+      for enum_type in &file.enum_type {
+        descriptors.enums.insert(format!("{}{}", prefix, enum_type.name()), enum_type.clone());
+      }
+      for service in &file.service {
+        for method in &service.method {
+          descriptors.methods.insert(format!("{}{}.{}", prefix, service.name(), method.name()),
+            (qualified_name(method.input_type()).to_string(), qualified_name(method.output_type()).to_string()));
+        }
+      }
+    }
+    Ok(descriptors)
+  }
+
+  fn add_message(&mut self, prefix: &str, message: &DescriptorProto) {
+    let name = format!("{}{}", prefix, message.name());
+    let nested_prefix = format!("{}.", name);
+    for nested in &message.nested_type {
+      self.add_message(nested_prefix.as_str(), nested);
+    }
+    for enum_type in &message.enum_type {
+      self.enums.insert(format!("{}{}", nested_prefix, enum_type.name()), enum_type.clone());
+    }
+    self.messages.insert(name, message.clone());
+  }
+
+  fn message(&self, type_name: &str) -> anyhow::Result<&DescriptorProto> {
+    self.messages.get(type_name)
+      .ok_or_else(|| anyhow!("Message type '{}' is not defined in the plugin proto file", type_name))
+  }
+
+  fn is_map_field(&self, field: &FieldDescriptorProto) -> bool {
+    field.label() == Label::Repeated && field.r#type() == Type::Message &&
+      self.messages.get(qualified_name(field.type_name()))
+        .and_then(|message| message.options.as_ref())
+        .map(|options| options.map_entry())
+        .unwrap_or(false)
+  }
+
+  fn decode_message(&self, type_name: &str, mut buffer: &[u8]) -> anyhow::Result<Value> {
+    match type_name {
+      "google.protobuf.Struct" => return Ok(proto_struct_to_json(&prost_types::Struct::decode(buffer)?)),
+      "google.protobuf.Value" => return Ok(proto_value_to_json(&prost_types::Value::decode(buffer)?)),
+      "google.protobuf.ListValue" => return Ok(Value::Array(prost_types::ListValue::decode(buffer)?
+        .values.iter().map(proto_value_to_json).collect())),
+      _ => {}
+    }
+
+    let descriptor = self.message(type_name)?;
+    let mut fields = Map::new();
+    while buffer.has_remaining() {
+      let (number, wire_type) = decode_key(&mut buffer)?;
+      let value = read_wire_value(wire_type, &mut buffer)?;
+      // Unknown fields are ignored
+      if let Some(field) = descriptor.field.iter().find(|field| field.number() == number as i32) {
+        self.add_field_json(field, value, &mut fields)?;
+      }
+    }
+
+    if type_name == BYTES_VALUE {
+      Ok(fields.remove("value").unwrap_or_else(|| Value::String(String::default())))
+    } else {
+      Ok(Value::Object(fields))
+    }
+  }
+
+  fn add_field_json(
+    &self,
+    field: &FieldDescriptorProto,
+    value: WireValue,
+    fields: &mut Map<String, Value>
+  ) -> anyhow::Result<()> {
+    if self.is_map_field(field) {
+      let entry = match value {
+        WireValue::LengthDelimited(bytes) => self.decode_message(qualified_name(field.type_name()), bytes)?,
+        _ => return Err(anyhow!("Map field '{}' has an invalid wire type", field.name()))
+      };
+      let entry_descriptor = self.message(qualified_name(field.type_name()))?;
+      let key = match entry.get("key") {
+        Some(Value::String(key)) => key.clone(),
+        Some(key) => key.to_string(),
+        None => String::default()
+      };
+      let value = entry.get("value").cloned()
+        .or_else(|| entry_descriptor.field.iter().find(|field| field.number() == 2).map(default_json))
+        .unwrap_or_default();
+      if let Value::Object(map) = fields.entry(json_name(field)).or_insert_with(|| Value::Object(Map::new())) {
+        map.insert(key, value);
+      }
+    } else if field.label() == Label::Repeated {
+      let mut values = vec![];
+      match value {
+        WireValue::LengthDelimited(mut bytes) if is_packable(field) => {
+          while bytes.has_remaining() {
+            let value = read_wire_value(WireType::Varint, &mut bytes)?;
+            values.push(self.field_value_json(field, value)?);
+          }
+        }
+        _ => values.push(self.field_value_json(field, value)?)
+      }
+      if let Value::Array(array) = fields.entry(json_name(field)).or_insert_with(|| Value::Array(vec![])) {
+        array.extend(values);
+      }
+    } else {
+      fields.insert(json_name(field), self.field_value_json(field, value)?);
+    }
+    Ok(())
+  }
+
+  fn field_value_json(&self, field: &FieldDescriptorProto, value: WireValue) -> anyhow::Result<Value> {
+    Ok(match (field.r#type(), value) {
+      (Type::Int32, WireValue::Varint(v)) => Value::from(v as i32),
+      (Type::Uint32, WireValue::Varint(v)) => Value::from(v as u32),
+      (Type::Bool, WireValue::Varint(v)) => Value::Bool(v != 0),
+      (Type::String, WireValue::LengthDelimited(bytes)) => Value::String(String::from_utf8(bytes.to_vec())?),
+      (Type::Bytes, WireValue::LengthDelimited(bytes)) => Value::String(base64::encode(bytes)),
+      (Type::Enum, WireValue::Varint(v)) => self.enum_json(field.type_name(), v as i32),
+      (Type::Message, WireValue::LengthDelimited(bytes)) => self.decode_message(qualified_name(field.type_name()), bytes)?,
+      (Type::Int32 | Type::Uint32 | Type::Bool | Type::String | Type::Bytes | Type::Enum | Type::Message, _) =>
+        return Err(anyhow!("Field '{}' of type {:?} has an invalid wire type", field.name(), field.r#type())),
+      (field_type, _) => return Err(unsupported_type(field, field_type))
+    })
+  }
+
+  fn enum_json(&self, type_name: &str, number: i32) -> Value {
+    self.enums.get(qualified_name(type_name))
+      .and_then(|enum_type| enum_type.value.iter().find(|value| value.number() == number))
+      .map(|value| Value::String(value.name().to_string()))
+      .unwrap_or_else(|| Value::from(number))
+  }
+
+  fn encode_message(&self, type_name: &str, json: &Value, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
+    match type_name {
+      "google.protobuf.Struct" => {
+        let fields = json.as_object()
+          .ok_or_else(|| anyhow!("Expected a JSON object for a Struct, but got {}", json))?;
+        return Ok(to_proto_struct(fields.clone().into_iter().collect()).encode(buffer)?);
+      }
+      "google.protobuf.Value" => return Ok(to_proto_value(json).encode(buffer)?),
+      "google.protobuf.ListValue" => {
+        let values = json.as_array()
+          .ok_or_else(|| anyhow!("Expected a JSON array for a ListValue, but got {}", json))?;
+        return Ok(prost_types::ListValue { values: values.iter().map(to_proto_value).collect() }.encode(buffer)?);
+      }
+      _ => {}
+    }
+
+    let descriptor = self.message(type_name)?;
+    if type_name == BYTES_VALUE {
+      return match descriptor.field.first() {
+        Some(field) => self.encode_field(field, json, buffer),
+        None => Err(anyhow!("{} does not have a value field", type_name))
+      };
+    }
+
+    let fields = json.as_object()
+      .ok_or_else(|| anyhow!("Expected a JSON object for {}, but got {}", type_name, json))?;
+    for (name, value) in fields {
+      let field = descriptor.field.iter()
+        .find(|field| json_name(field) == *name || field.name() == name)
+        .ok_or_else(|| anyhow!("{} does not have a field '{}'", type_name, name))?;
+      // Null is the default value of a field, except for google.protobuf.Value where it is the null value
+      if value.is_null() && field.type_name() != ".google.protobuf.Value" {
+        continue;
+      }
+
+      if self.is_map_field(field) {
+        let entries = value.as_object()
+          .ok_or_else(|| anyhow!("Expected a JSON object for map field '{}', but got {}", name, value))?;
+        let entry_descriptor = self.message(qualified_name(field.type_name()))?;
+        let entry_field = |number: i32| entry_descriptor.field.iter().find(|field| field.number() == number)
+          .ok_or_else(|| anyhow!("Map entry for field '{}' does not have a key and value", name));
+        let (key_field, value_field) = (entry_field(1)?, entry_field(2)?);
+        for (key, value) in entries {
+          let mut entry = vec![];
+          self.encode_field(key_field, &Value::String(key.clone()), &mut entry)?;
+          self.encode_field(value_field, value, &mut entry)?;
+          encode_key(field.number() as u32, WireType::LengthDelimited, buffer);
+          encode_varint(entry.len() as u64, buffer);
+          buffer.extend(entry);
+        }
+      } else if field.label() == Label::Repeated {
+        let values = value.as_array()
+          .ok_or_else(|| anyhow!("Expected a JSON array for repeated field '{}', but got {}", name, value))?;
+        for value in values {
+          self.encode_field(field, value, buffer)?;
+        }
+      } else {
+        self.encode_field(field, value, buffer)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn encode_field(&self, field: &FieldDescriptorProto, json: &Value, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
+    let number = field.number() as u32;
+    let field_error = |err: anyhow::Error| anyhow!("Field '{}' has an invalid value - {}", field.name(), err);
+    match field.r#type() {
+      Type::Int32 => {
+        let value = json_integer::<i32>(json).map_err(field_error)?;
+        encode_key(number, WireType::Varint, buffer);
+        encode_varint(value as i64 as u64, buffer);
+      }
+      Type::Uint32 => {
+        let value = json_integer::<u32>(json).map_err(field_error)?;
+        encode_key(number, WireType::Varint, buffer);
+        encode_varint(value as u64, buffer);
+      }
+      Type::Bool => {
+        let value = match json {
+          Value::Bool(b) => *b,
+          Value::String(s) if s == "true" || s == "false" => s == "true",
+          _ => return Err(field_error(anyhow!("Expected a boolean, but got {}", json)))
+        };
+        encode_key(number, WireType::Varint, buffer);
+        encode_varint(value as u64, buffer);
+      }
+      Type::String => {
+        let value = json.as_str()
+          .ok_or_else(|| field_error(anyhow!("Expected a string, but got {}", json)))?;
+        encode_length_delimited(number, value.as_bytes(), buffer);
+      }
+      Type::Bytes => {
+        let value = json.as_str()
+          .ok_or_else(|| field_error(anyhow!("Expected a Base64 encoded string, but got {}", json)))?;
+        let bytes = base64::decode(value)
+          .or_else(|_| base64::decode_config(value, base64::URL_SAFE))
+          .map_err(|err| field_error(anyhow!(err)))?;
+        encode_length_delimited(number, bytes.as_slice(), buffer);
+      }
+      Type::Enum => {
+        let value = self.enum_number(field.type_name(), json).map_err(field_error)?;
+        encode_key(number, WireType::Varint, buffer);
+        encode_varint(value as i64 as u64, buffer);
+      }
+      Type::Message => {
+        let mut message = vec![];
+        self.encode_message(qualified_name(field.type_name()), json, &mut message)?;
+        encode_length_delimited(number, message.as_slice(), buffer);
+      }
+      field_type => return Err(unsupported_type(field, field_type))
+    }
+    Ok(())
+  }
+
+  fn enum_number(&self, type_name: &str, json: &Value) -> anyhow::Result<i32> {
+    match json {
+      Value::String(name) => self.enums.get(qualified_name(type_name))
+        .and_then(|enum_type| enum_type.value.iter().find(|value| value.name() == name))
+        .map(|value| value.number())
+        .ok_or_else(|| anyhow!("'{}' is not a value of {}", name, qualified_name(type_name))),
+      _ => json_integer(json)
+    }
+  }
+}
+
+fn qualified_name(type_name: &str) -> &str {
+  type_name.trim_start_matches('.')
+}
+
+fn json_name(field: &FieldDescriptorProto) -> String {
+  if field.json_name().is_empty() {
+    let mut name = String::default();
+    let mut upper = false;
+    for ch in field.name().chars() {
+      if ch == '_' {
+        upper = true;
+      } else if upper {
+        name.push(ch.to_ascii_uppercase());
+        upper = false;
+      } else {
+        name.push(ch);
+      }
+    }
+    name
+  } else {
+    field.json_name().to_string()
+  }
+}
+
+fn default_json(field: &FieldDescriptorProto) -> Value {
+  match field.r#type() {
+    Type::Bool => Value::Bool(false),
+    Type::String | Type::Bytes => Value::String(String::default()),
+    Type::Message => Value::Object(Map::new()),
+    _ => Value::from(0)
+  }
+}
+
+// Only the scalar types that use the varint wire type are used in repeated fields
+fn is_packable(field: &FieldDescriptorProto) -> bool {
+  matches!(field.r#type(), Type::Int32 | Type::Uint32 | Type::Bool | Type::Enum)
+}
+
+fn read_wire_value<'a>(wire_type: WireType, buffer: &mut &'a [u8]) -> anyhow::Result<WireValue<'a>> {
+  match wire_type {
+    WireType::Varint => Ok(WireValue::Varint(decode_varint(buffer)?)),
+    WireType::LengthDelimited => {
+      let length = decode_varint(buffer)? as usize;
+      if length > buffer.len() {
+        return Err(anyhow!("Message is truncated"));
+      }
+      let (bytes, rest) = buffer.split_at(length);
+      *buffer = rest;
+      Ok(WireValue::LengthDelimited(bytes))
+    }
+    wire_type => Err(anyhow!("Wire type {:?} is not used by the plugin proto file", wire_type))
+  }
+}
+
+fn encode_length_delimited(number: u32, bytes: &[u8], buffer: &mut Vec<u8>) {
+  encode_key(number, WireType::LengthDelimited, buffer);
+  encode_varint(bytes.len() as u64, buffer);
+  buffer.extend_from_slice(bytes);
+}
+
+fn unsupported_type(field: &FieldDescriptorProto, field_type: Type) -> anyhow::Error {
+  anyhow!("Field '{}' has type {:?}, which is not used by the plugin proto file", field.name(), field_type)
+}
+
+// Integers can be either JSON numbers or strings
+fn json_integer<T: TryFrom<i64>>(json: &Value) -> anyhow::Result<T> {
+  let value = match json {
+    Value::Number(n) => n.as_i64()
+      .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+      .ok_or_else(|| anyhow!("{} is not a valid integer", n))?,
+    Value::String(s) => s.parse()?,
+    _ => return Err(anyhow!("Expected an integer, but got {}", json))
+  };
+  T::try_from(value).map_err(|_| anyhow!("{} is out of range", value))
+}
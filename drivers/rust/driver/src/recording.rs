@@ -0,0 +1,310 @@
+//! Support for recording the calls made to plugins, and replaying them without the plugin. This is
+//! used to reproduce problems with plugins (i.e. from a CI build) offline.
+//!
+//! Recording is enabled by setting the `PACT_PLUGIN_RECORDING_FILE` environment variable, or by
+//! calling `start_recording`. Every request and response sent to a plugin is then appended to the
+//! file as a line of JSON (see `RecordedCall`), with the messages in the Protobuf JSON format. The
+//! recording can be loaded with `ReplayPlugin`, which serves the recorded responses to the
+//! `_with_plugin` functions.
+
+use std::collections::HashSet;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::plugin_models::{PactPluginManifest, PactPluginRpc};
+use crate::proto::*;
+use crate::proto_json::{message_from_json, message_to_json, method_types};
+
+lazy_static! {
+  static ref RECORDING_FILE: Mutex<Option<File>> = Mutex::new(recording_file_from_env());
+}
+
+/// A call to a plugin that was recorded
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCall {
+  /// Plugin name
+  pub plugin: String,
+  /// Plugin version
+  pub version: String,
+  /// Name of the RPC method that was called (i.e. CompareContents)
+  pub method: String,
+  /// Request sent to the plugin
+  pub request: Value,
+  /// Response returned by the plugin, if the call was successful
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub response: Option<Value>,
+  /// Error from the call, if it failed
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>
+}
+
+fn recording_file_from_env() -> Option<File> {
+  env::var_os("PACT_PLUGIN_RECORDING_FILE").and_then(|path| {
+    match open_recording_file(Path::new(&path)) {
+      Ok(file) => {
+        debug!("Recording plugin calls to {:?}", path);
+        Some(file)
+      },
+      Err(err) => {
+        warn!("Could not open plugin recording file {:?} - {}", path, err);
+        None
+      }
+    }
+  })
+}
+
+fn open_recording_file(path: &Path) -> anyhow::Result<File> {
+  Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// Start recording all the calls to plugins to the given file. The calls are appended to the
+/// file if it already exists. This replaces any file currently being recorded to.
+pub fn start_recording<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
+  let file = open_recording_file(path.as_ref())?;
+  debug!("Recording plugin calls to {:?}", path.as_ref());
+  *RECORDING_FILE.lock().unwrap() = Some(file);
+  Ok(())
+}
+
+/// Stop recording calls to plugins
+pub fn stop_recording() {
+  *RECORDING_FILE.lock().unwrap() = None;
+}
+
+/// If calls to plugins are being recorded
+pub fn is_recording() -> bool {
+  RECORDING_FILE.lock().unwrap().is_some()
+}
+
+/// Makes the call to the plugin, recording the request and response (or error) if recording is
+/// enabled. Failures to record the call are logged and otherwise ignored.
+pub(crate) async fn record_call<Req, Res, F, Fut>(
+  manifest: &PactPluginManifest,
+  method: &str,
+  request: Req,
+  call: F
+) -> anyhow::Result<Res>
+  where Req: Message,
+        Res: Message,
+        F: FnOnce(Req) -> Fut,
+        Fut: Future<Output = anyhow::Result<Res>> {
+  let recorded_request = if is_recording() {
+    request_to_json(method, &request).map_err(|err| warn!("Could not record the {} request - {}", method, err)).ok()
+  } else {
+    None
+  };
+
+  let result = call(request).await;
+
+  if let Some(request) = recorded_request {
+    let mut call = RecordedCall {
+      plugin: manifest.name.clone(),
+      version: manifest.version.clone(),
+      method: method.to_string(),
+      request,
+      .. RecordedCall::default()
+    };
+    match &result {
+      Ok(response) => call.response = response_to_json(method, response)
+        .map_err(|err| warn!("Could not record the {} response - {}", method, err)).ok(),
+      Err(err) => call.error = Some(err.to_string())
+    }
+    write_call(&call);
+  }
+
+  result
+}
+
+fn rpc_message_types(method: &str) -> anyhow::Result<(&'static str, &'static str)> {
+  method_types(format!("io.pact.plugin.PactPlugin.{}", method).as_str())
+    .ok_or_else(|| anyhow!("'{}' is not a method of the PactPlugin service", method))
+}
+
+/// Converts a request for the PactPlugin RPC method (i.e. CompareContents) to the Protobuf JSON
+/// format used in the recordings
+pub fn request_to_json<Req: Message>(method: &str, request: &Req) -> anyhow::Result<Value> {
+  message_to_json(rpc_message_types(method)?.0, request)
+}
+
+/// Converts a response from the PactPlugin RPC method (i.e. CompareContents) to the Protobuf JSON
+/// format used in the recordings
+pub fn response_to_json<Res: Message>(method: &str, response: &Res) -> anyhow::Result<Value> {
+  message_to_json(rpc_message_types(method)?.1, response)
+}
+
+fn write_call(call: &RecordedCall) {
+  let mut guard = RECORDING_FILE.lock().unwrap();
+  if let Some(file) = guard.as_mut() {
+    let result = serde_json::to_string(call)
+      .map_err(|err| anyhow!(err))
+      .and_then(|json| writeln!(file, "{}", json).and_then(|_| file.flush()).map_err(|err| anyhow!(err)));
+    if let Err(err) = result {
+      warn!("Could not write the {} call to the plugin recording file - {}", call.method, err);
+    }
+  }
+}
+
+/// Implementation of `PactPluginRpc` that returns the responses from a recording of the calls to a
+/// plugin. Each recorded call is only used once. The response for a request is taken from the
+/// first unused call for the same method with an equal request, and an error is returned if there
+/// is no such call, so a replay that diverges from the recording fails.
+pub struct ReplayPlugin {
+  calls: Vec<RecordedCall>,
+  used: Mutex<HashSet<usize>>
+}
+
+impl ReplayPlugin {
+  /// Create a replay plugin from the recorded calls
+  pub fn new(calls: Vec<RecordedCall>) -> Self {
+    ReplayPlugin {
+      calls,
+      used: Mutex::new(HashSet::new())
+    }
+  }
+
+  /// Load the calls recorded for the named plugin from a recording file. If the plugin name is not
+  /// given, all the calls in the file are loaded.
+  pub fn load<P: AsRef<Path>>(path: P, plugin: Option<&str>) -> anyhow::Result<Self> {
+    let file = File::open(path.as_ref())?;
+    let mut calls = vec![];
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if !line.trim().is_empty() {
+        let call: RecordedCall = serde_json::from_str(line.as_str())
+          .map_err(|err| anyhow!("Could not parse the recorded plugin call '{}' - {}", line, err))?;
+        if plugin.map(|name| name == call.plugin).unwrap_or(true) {
+          calls.push(call);
+        }
+      }
+    }
+    debug!("Loaded {} recorded plugin calls from {:?}", calls.len(), path.as_ref());
+    Ok(ReplayPlugin::new(calls))
+  }
+
+  fn replay<Req, Res>(&self, method: &str, request: &Req) -> anyhow::Result<Res>
+    where Req: Message + Default + PartialEq,
+          Res: Message + Default {
+    self.replay_matching(method, |recorded: &Req| recorded == request)
+  }
+
+  fn replay_matching<Req, Res, F>(&self, method: &str, matches: F) -> anyhow::Result<Res>
+    where Req: Message + Default,
+          Res: Message + Default,
+          F: Fn(&Req) -> bool {
+    let (request_type, response_type) = rpc_message_types(method)?;
+    let mut used = self.used.lock().unwrap();
+    let mut unused = self.calls.iter().enumerate()
+      .filter(|(index, call)| call.method == method && !used.contains(index))
+      .peekable();
+    if unused.peek().is_none() {
+      return Err(anyhow!("There is no recorded {} call to replay", method));
+    }
+
+    let found = unused.find(|(_, call)| {
+      match message_from_json::<Req>(request_type, &call.request) {
+        Ok(request) => matches(&request),
+        Err(err) => {
+          debug!("Ignoring recorded {} call as the request could not be loaded - {}", method, err);
+          false
+        }
+      }
+    });
+    match found {
+      Some((index, call)) => {
+        used.insert(index);
+        match (&call.response, &call.error) {
+          (Some(response), _) => message_from_json(response_type, response),
+          (None, Some(err)) => Err(anyhow!(err.clone())),
+          (None, None) => Err(anyhow!("Recorded {} call does not have a response", method))
+        }
+      }
+      None => Err(anyhow!("None of the recorded {} calls match the request", method))
+    }
+  }
+}
+
+#[async_trait]
+impl PactPluginRpc for ReplayPlugin {
+  /// Capabilities the plugin returned in the recorded init call, if there is one
+  fn capabilities(&self) -> Option<Vec<String>> {
+    self.calls.iter()
+      .find(|call| call.method == "InitPlugin")
+      .and_then(|call| call.response.as_ref())
+      .and_then(|response| message_from_json::<InitPluginResponse>("io.pact.plugin.InitPluginResponse", response).ok())
+      .map(|response| response.capabilities)
+      .filter(|capabilities| !capabilities.is_empty())
+  }
+
+  /// The callback service details (and the capability advertising it) change between runs, so are
+  /// not compared
+  async fn init_plugin(&self, request: InitPluginRequest) -> anyhow::Result<InitPluginResponse> {
+    let without_callback_service = |request: &InitPluginRequest| InitPluginRequest {
+      callback_address: String::default(),
+      callback_token: String::default(),
+      capabilities: vec![],
+      .. request.clone()
+    };
+    let request = without_callback_service(&request);
+    self.replay_matching("InitPlugin", |recorded: &InitPluginRequest| without_callback_service(recorded) == request)
+  }
+
+  async fn compare_contents(&self, request: CompareContentsRequest) -> anyhow::Result<CompareContentsResponse> {
+    self.replay("CompareContents", &request)
+  }
+
+  async fn compare_contents_batch(&self, request: CompareContentsBatchRequest) -> anyhow::Result<CompareContentsBatchResponse> {
+    self.replay("CompareContentsBatch", &request)
+  }
+
+  async fn compare_stream(&self, request: CompareStreamRequest) -> anyhow::Result<CompareStreamResponse> {
+    self.replay("CompareStream", &request)
+  }
+
+  async fn configure_interaction(&self, request: ConfigureInteractionRequest) -> anyhow::Result<ConfigureInteractionResponse> {
+    self.replay("ConfigureInteraction", &request)
+  }
+
+  async fn generate_content(&self, request: GenerateContentRequest) -> anyhow::Result<GenerateContentResponse> {
+    self.replay("GenerateContent", &request)
+  }
+
+  async fn match_value(&self, request: MatchValueRequest) -> anyhow::Result<MatchValueResponse> {
+    self.replay("MatchValue", &request)
+  }
+
+  async fn generate_value(&self, request: GenerateValueRequest) -> anyhow::Result<GenerateValueResponse> {
+    self.replay("GenerateValue", &request)
+  }
+
+  async fn start_mock_server(&self, request: StartMockServerRequest) -> anyhow::Result<StartMockServerResponse> {
+    self.replay("StartMockServer", &request)
+  }
+
+  async fn shutdown_mock_server(&self, request: ShutdownMockServerRequest) -> anyhow::Result<ShutdownMockServerResponse> {
+    self.replay("ShutdownMockServer", &request)
+  }
+
+  async fn get_mock_server_results(&self, request: MockServerRequest) -> anyhow::Result<MockServerResults> {
+    self.replay("GetMockServerResults", &request)
+  }
+
+  async fn prepare_interaction_for_verification(&self, request: VerificationPreparationRequest) -> anyhow::Result<VerificationPreparationResponse> {
+    self.replay("PrepareInteractionForVerification", &request)
+  }
+
+  async fn verify_interaction(&self, request: VerifyInteractionRequest) -> anyhow::Result<VerifyInteractionResponse> {
+    self.replay("VerifyInteraction", &request)
+  }
+}
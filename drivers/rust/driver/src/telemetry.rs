@@ -0,0 +1,157 @@
+//! Support for tracing the calls to plugins with OpenTelemetry. The driver operations are wrapped
+//! in `tracing` spans, and the trace context is sent to plugins in the gRPC metadata (using the
+//! W3C `traceparent` header), so plugins can continue the trace.
+//!
+//! The OpenTelemetry support needs the `otel` feature. Without it, the spans are still created, but
+//! no trace context is sent to plugins and the spans can not be exported with `init_telemetry`.
+//!
+//! The spans are only exported if the host calls `init_telemetry`, either to an OTLP collector or
+//! to a file. Hosts that install their own `tracing` subscriber can instead add the layer from
+//! `tracing-opentelemetry` to it and set the global text map propagator. Plugins written in Rust
+//! can use the same functions to export their spans, and `set_parent_from_headers` to continue the
+//! trace from the driver.
+
+#[cfg(feature = "otel")] use std::env;
+#[cfg(feature = "otel")] use std::fs::OpenOptions;
+#[cfg(feature = "otel")] use std::path::PathBuf;
+
+#[cfg(feature = "otel")] use anyhow::anyhow;
+#[cfg(feature = "otel")] use log::debug;
+#[cfg(feature = "otel")] use opentelemetry::global;
+#[cfg(feature = "otel")] use opentelemetry::KeyValue;
+#[cfg(feature = "otel")] use opentelemetry::propagation::{Extractor, Injector};
+#[cfg(feature = "otel")] use opentelemetry::sdk::export::trace::stdout;
+#[cfg(feature = "otel")] use opentelemetry::sdk::propagation::TraceContextPropagator;
+#[cfg(feature = "otel")] use opentelemetry::sdk::Resource;
+#[cfg(feature = "otel")] use opentelemetry::sdk::trace::{Config, Tracer};
+#[cfg(feature = "otel")] use opentelemetry_otlp::WithExportConfig;
+use tonic::codegen::http::HeaderMap;
+#[cfg(feature = "otel")] use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+#[cfg(feature = "otel")] use tracing_opentelemetry::OpenTelemetrySpanExt;
+#[cfg(feature = "otel")] use tracing_subscriber::layer::SubscriberExt;
+
+/// Where to export the trace spans to
+#[cfg(feature = "otel")]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TelemetryExporter {
+  /// Export to an OTLP collector using gRPC (i.e. http://localhost:4317)
+  Otlp(String),
+  /// Append the spans to a file, one line per span
+  File(PathBuf)
+}
+
+#[cfg(feature = "otel")]
+impl TelemetryExporter {
+  /// Exporter configured with the `PACT_PLUGIN_OTLP_ENDPOINT` or `PACT_PLUGIN_TRACE_FILE`
+  /// environment variables, or `None` if neither is set. Plugins are started with the same
+  /// environment, so can use the same variables.
+  pub fn from_env() -> Option<TelemetryExporter> {
+    env::var("PACT_PLUGIN_OTLP_ENDPOINT").ok()
+      .filter(|endpoint| !endpoint.is_empty())
+      .map(TelemetryExporter::Otlp)
+      .or_else(|| env::var_os("PACT_PLUGIN_TRACE_FILE")
+        .filter(|path| !path.is_empty())
+        .map(|path| TelemetryExporter::File(PathBuf::from(path))))
+  }
+}
+
+/// Export the trace spans with the given exporter, installing a global `tracing` subscriber and
+/// the W3C trace context propagator. The service name is used to identify the spans from the host.
+/// The OTLP exporter must be set up from within a Tokio runtime that lives as long as the spans are
+/// being exported.
+///
+/// Returns an error if a global `tracing` subscriber has already been installed.
+#[cfg(feature = "otel")]
+pub fn init_telemetry(exporter: TelemetryExporter, service_name: &str) -> anyhow::Result<()> {
+  debug!("Exporting trace spans to {:?}", exporter);
+  let config = Config::default()
+    .with_resource(Resource::new(vec![KeyValue::new("service.name", service_name.to_string())]));
+  let tracer: Tracer = match exporter {
+    TelemetryExporter::Otlp(endpoint) => {
+      tokio::runtime::Handle::try_current()
+        .map_err(|_| anyhow!("The OTLP exporter must be set up from within a Tokio runtime"))?;
+      opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+        .with_trace_config(config)
+        .install_batch(opentelemetry::runtime::TokioCurrentThread)?
+    }
+    TelemetryExporter::File(path) => {
+      let file = OpenOptions::new().create(true).append(true).open(path)?;
+      stdout::new_pipeline()
+        .with_writer(file)
+        .with_trace_config(config)
+        .install_simple()
+    }
+  };
+
+  global::set_text_map_propagator(TraceContextPropagator::new());
+  let subscriber = tracing_subscriber::registry()
+    .with(tracing_opentelemetry::layer().with_tracer(tracer));
+  tracing::subscriber::set_global_default(subscriber)
+    .map_err(|err| anyhow!("Could not install the tracing subscriber - {}", err))
+}
+
+/// Export any remaining spans and shut down the exporter
+#[cfg(feature = "otel")]
+pub fn shutdown_telemetry() {
+  global::shutdown_tracer_provider();
+}
+
+/// Creates the gRPC request to send to a plugin, adding the trace context of the current span to
+/// the request metadata
+#[cfg(feature = "otel")]
+pub(crate) fn traced_request<T>(message: T) -> tonic::Request<T> {
+  let mut request = tonic::Request::new(message);
+  let context = tracing::Span::current().context();
+  global::get_text_map_propagator(|propagator| {
+    propagator.inject_context(&context, &mut MetadataInjector(request.metadata_mut()))
+  });
+  request
+}
+
+/// Creates the gRPC request to send to a plugin
+#[cfg(not(feature = "otel"))]
+pub(crate) fn traced_request<T>(message: T) -> tonic::Request<T> {
+  tonic::Request::new(message)
+}
+
+/// Sets the parent of the span to the trace context received in the headers of a gRPC request
+/// (i.e. in the `trace_fn` of a tonic server)
+#[cfg(feature = "otel")]
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+  let context = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+  span.set_parent(context);
+}
+
+/// Sets the parent of the span to the trace context received in the headers of a gRPC request.
+/// This does nothing without the `otel` feature.
+#[cfg(not(feature = "otel"))]
+pub fn set_parent_from_headers(_span: &tracing::Span, _headers: &HeaderMap) {}
+
+#[cfg(feature = "otel")]
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+#[cfg(feature = "otel")]
+impl<'a> Injector for MetadataInjector<'a> {
+  fn set(&mut self, key: &str, value: String) {
+    if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), MetadataValue::from_str(value.as_str())) {
+      self.0.insert(key, value);
+    }
+  }
+}
+
+/// Extracts the trace context from the headers of a gRPC request
+#[cfg(feature = "otel")]
+pub struct HeaderExtractor<'a>(pub &'a HeaderMap);
+
+#[cfg(feature = "otel")]
+impl<'a> Extractor for HeaderExtractor<'a> {
+  fn get(&self, key: &str) -> Option<&str> {
+    self.0.get(key).and_then(|value| value.to_str().ok())
+  }
+
+  fn keys(&self) -> Vec<&str> {
+    self.0.keys().map(|key| key.as_str()).collect()
+  }
+}
@@ -27,7 +27,7 @@ pub fn to_proto_value(val: &Value) -> prost_types::Value {
     }
     Value::String(s) => prost_types::Value { kind: Some(prost_types::value::Kind::StringValue(s.clone())) },
     Value::Array(a) => prost_types::Value { kind: Some(prost_types::value::Kind::ListValue(ListValue {
-      values: a.iter().map(|v| to_proto_value(v)).collect()
+      values: a.iter().map(to_proto_value).collect()
     }))},
     Value::Object(o) => prost_types::Value { kind: Some(prost_types::value::Kind::StructValue(Struct {
       fields: o.iter().map(|(k, v)| (k.clone(), to_proto_value(v))).collect()
@@ -51,7 +51,7 @@ pub fn proto_value_to_json(val: &prost_types::Value) -> Value {
       Kind::BoolValue(b) => Value::Bool(*b),
       Kind::StructValue(s) => proto_struct_to_json(s),
       Kind::ListValue(l) => Value::Array(l.values.iter()
-        .map(|v| proto_value_to_json(v)).collect())
+        .map(proto_value_to_json).collect())
     }
     None => Value::Null
   }
@@ -72,7 +72,7 @@ pub fn proto_value_to_string(val: &prost_types::Value) -> Option<String> {
       Kind::BoolValue(b) => Some(b.to_string()),
       Kind::StructValue(s) => Some(proto_struct_to_json(s).to_string()),
       Kind::ListValue(l) => Some(Value::Array(l.values.iter()
-        .map(|v| proto_value_to_json(v)).collect()).to_string())
+        .map(proto_value_to_json).collect()).to_string())
     }
     None => None
   }
@@ -0,0 +1,247 @@
+//! Support for verifying interactions against a provider using a plugin (i.e. for protocols like gRPC)
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use log::{debug, error};
+use pact_models::bodies::OptionalBody;
+use pact_models::PactSpecification;
+use pact_models::prelude::Pact;
+use pact_models::v4::interaction::V4Interaction;
+use serde_json::Value;
+
+use crate::cancellation::{CancellationToken, with_cancellation};
+use crate::catalogue_manager::CatalogueEntry;
+use crate::content::{ContentMismatch, from_proto_body, to_proto_body};
+use crate::plugin_manager::lookup_plugin;
+use crate::plugin_models::{CAPABILITY_VERIFY_INTERACTION, PactPluginManifest, PactPluginRpc};
+use crate::proto::{
+  InteractionData,
+  MetadataValue as ProtoMetadataValue,
+  VerificationPreparationRequest,
+  VerificationResultItem,
+  VerifyInteractionRequest
+};
+use crate::proto::metadata_value;
+use crate::proto::verification_preparation_response;
+use crate::proto::verification_result_item;
+use crate::proto::verify_interaction_response;
+use crate::utils::{proto_value_to_json, to_proto_struct, to_proto_value};
+
+/// Value of a metadata entry for a request or response. Metadata values can be JSON or binary data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+  /// JSON value
+  Json(Value),
+  /// Binary data
+  Binary(Bytes)
+}
+
+/// Data required to execute the verification of an interaction
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InteractionVerificationData {
+  /// Data for the request of the interaction
+  pub request_data: OptionalBody,
+  /// Metadata associated with the request
+  pub metadata: HashMap<String, MetadataValue>
+}
+
+/// Details of an error or mismatch from verifying an interaction
+#[derive(Clone, Debug)]
+pub enum InteractionVerificationDetails {
+  /// Error occurred while verifying the interaction
+  Error(String),
+  /// Mismatch with the response from the provider
+  Mismatch(ContentMismatch)
+}
+
+/// Result of running an interaction verification
+#[derive(Clone, Debug, Default)]
+pub struct InteractionVerificationResult {
+  /// If the verification was successful
+  pub ok: bool,
+  /// Any errors or mismatches that occurred
+  pub details: Vec<InteractionVerificationDetails>,
+  /// Output to display to the user
+  pub output: Vec<String>,
+  /// Data that was received from the provider, if the plugin returned it
+  pub response_data: Option<InteractionVerificationData>
+}
+
+/// Prepare the request data for verifying the interaction with the plugin that provides the
+/// transport for the interaction (the same catalogue entry as `find_mock_server`). The returned
+/// data can be modified (i.e. to inject provider state values or auth headers) before being passed
+/// to `verify_interaction`. The call is aborted if the cancellation token is cancelled.
+pub async fn prepare_validation_for_interaction(
+  transport_entry: &CatalogueEntry,
+  pact: &dyn Pact,
+  interaction: &dyn V4Interaction,
+  config: &HashMap<String, Value>,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<InteractionVerificationData> {
+  let plugin_manifest = transport_plugin(transport_entry)?;
+  match lookup_plugin(&plugin_manifest.as_dependency()) {
+    Some(plugin) => with_cancellation(cancellation_token,
+      prepare_validation_for_interaction_with_plugin(&plugin, transport_entry, pact, interaction, config)).await?,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", transport_entry);
+      Err(anyhow!("Plugin for {:?} was not found in the plugin register", transport_entry))
+    }
+  }
+}
+
+/// Internal function: public for testing. Sends the prepare interaction for verification request
+/// to the given plugin.
+pub async fn prepare_validation_for_interaction_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  transport_entry: &CatalogueEntry,
+  pact: &dyn Pact,
+  interaction: &dyn V4Interaction,
+  config: &HashMap<String, Value>
+) -> anyhow::Result<InteractionVerificationData> {
+  let plugin_manifest = check_supports_verification(plugin, transport_entry)?;
+  let request = VerificationPreparationRequest {
+    pact: pact_json(pact)?,
+    interaction_key: interaction_key(interaction),
+    config: Some(to_proto_struct(config.clone()))
+  };
+
+  debug!("Sending prepareInteractionForVerification request to plugin {:?}", plugin_manifest);
+  match plugin.prepare_interaction_for_verification(request).await?.response {
+    Some(verification_preparation_response::Response::InteractionData(data)) => Ok(data.into()),
+    Some(verification_preparation_response::Response::Error(err)) =>
+      Err(anyhow!("Plugin {}/{} failed to prepare the interaction for verification - {}",
+        plugin_manifest.name, plugin_manifest.version, err)),
+    None => Err(anyhow!("Plugin {}/{} did not return any interaction data", plugin_manifest.name,
+      plugin_manifest.version))
+  }
+}
+
+/// Execute the verification of the interaction against the provider using the plugin that provides
+/// the transport for the interaction. The configuration is supplied by the user and will contain
+/// the details required to connect to the provider (i.e. host and port). The call is aborted if the
+/// cancellation token is cancelled.
+pub async fn verify_interaction(
+  transport_entry: &CatalogueEntry,
+  verification_data: &InteractionVerificationData,
+  config: &HashMap<String, Value>,
+  pact: &dyn Pact,
+  interaction: &dyn V4Interaction,
+  cancellation_token: Option<&CancellationToken>
+) -> anyhow::Result<InteractionVerificationResult> {
+  let plugin_manifest = transport_plugin(transport_entry)?;
+  match lookup_plugin(&plugin_manifest.as_dependency()) {
+    Some(plugin) => with_cancellation(cancellation_token, verify_interaction_with_plugin(&plugin,
+      transport_entry, verification_data, config, pact, interaction)).await?,
+    None => {
+      error!("Plugin for {:?} was not found in the plugin register", transport_entry);
+      Err(anyhow!("Plugin for {:?} was not found in the plugin register", transport_entry))
+    }
+  }
+}
+
+/// Internal function: public for testing. Sends the verify interaction request to the given plugin.
+pub async fn verify_interaction_with_plugin(
+  plugin: &dyn PactPluginRpc,
+  transport_entry: &CatalogueEntry,
+  verification_data: &InteractionVerificationData,
+  config: &HashMap<String, Value>,
+  pact: &dyn Pact,
+  interaction: &dyn V4Interaction
+) -> anyhow::Result<InteractionVerificationResult> {
+  let plugin_manifest = check_supports_verification(plugin, transport_entry)?;
+  let request = VerifyInteractionRequest {
+    interaction_data: Some(verification_data.into()),
+    config: Some(to_proto_struct(config.clone())),
+    pact: pact_json(pact)?,
+    interaction_key: interaction_key(interaction)
+  };
+
+  debug!("Sending verifyInteraction request to plugin {:?}", plugin_manifest);
+  match plugin.verify_interaction(request).await?.response {
+    Some(verify_interaction_response::Response::Result(result)) => Ok(InteractionVerificationResult {
+      ok: result.success,
+      details: result.mismatches.iter().filter_map(verification_details).collect(),
+      output: result.output,
+      response_data: result.response_data.map(|data| data.into())
+    }),
+    Some(verify_interaction_response::Response::Error(err)) =>
+      Err(anyhow!("Plugin {}/{} failed to verify the interaction - {}", plugin_manifest.name,
+        plugin_manifest.version, err)),
+    None => Err(anyhow!("Plugin {}/{} did not return a verification result", plugin_manifest.name,
+      plugin_manifest.version))
+  }
+}
+
+fn check_supports_verification(
+  plugin: &dyn PactPluginRpc,
+  transport_entry: &CatalogueEntry
+) -> anyhow::Result<PactPluginManifest> {
+  let plugin_manifest = transport_plugin(transport_entry)?.clone();
+  if plugin.supports_for_entry(CAPABILITY_VERIFY_INTERACTION, transport_entry) {
+    Ok(plugin_manifest)
+  } else {
+    Err(anyhow!("Plugin {}/{} does not support verifying interactions", plugin_manifest.name,
+      plugin_manifest.version))
+  }
+}
+
+fn transport_plugin(transport_entry: &CatalogueEntry) -> anyhow::Result<&PactPluginManifest> {
+  transport_entry.plugin.as_ref()
+    .ok_or_else(|| anyhow!("Transport '{}' is provided by the core framework, not a plugin",
+      transport_entry.catalogue_key()))
+}
+
+fn pact_json(pact: &dyn Pact) -> anyhow::Result<String> {
+  Ok(pact.as_v4_pact()?.to_json(PactSpecification::V4)?.to_string())
+}
+
+/// Interactions without a key are stored in the Pact file with a calculated one, so use the same
+/// value the plugin will see.
+fn interaction_key(interaction: &dyn V4Interaction) -> String {
+  interaction.key().unwrap_or_else(|| {
+    match interaction.to_json().get("key") {
+      Some(Value::String(key)) => key.clone(),
+      _ => interaction.description()
+    }
+  })
+}
+
+fn verification_details(item: &VerificationResultItem) -> Option<InteractionVerificationDetails> {
+  item.result.as_ref().map(|result| match result {
+    verification_result_item::Result::Error(err) => InteractionVerificationDetails::Error(err.clone()),
+    verification_result_item::Result::Mismatch(mismatch) =>
+      InteractionVerificationDetails::Mismatch(mismatch.into())
+  })
+}
+
+impl From<InteractionData> for InteractionVerificationData {
+  fn from(data: InteractionData) -> Self {
+    InteractionVerificationData {
+      request_data: from_proto_body(data.body.as_ref()),
+      metadata: data.metadata.iter().filter_map(|(key, value)| {
+        value.value.as_ref().map(|value| match value {
+          metadata_value::Value::NonBinaryValue(value) => (key.clone(), MetadataValue::Json(proto_value_to_json(value))),
+          metadata_value::Value::BinaryValue(value) => (key.clone(), MetadataValue::Binary(Bytes::from(value.clone())))
+        })
+      }).collect()
+    }
+  }
+}
+
+impl From<&InteractionVerificationData> for InteractionData {
+  fn from(data: &InteractionVerificationData) -> Self {
+    InteractionData {
+      body: Some(to_proto_body(&data.request_data)),
+      metadata: data.metadata.iter().map(|(key, value)| {
+        (key.clone(), ProtoMetadataValue {
+          value: Some(match value {
+            MetadataValue::Json(value) => metadata_value::Value::NonBinaryValue(to_proto_value(value)),
+            MetadataValue::Binary(value) => metadata_value::Value::BinaryValue(value.to_vec())
+          })
+        })
+      }).collect()
+    }
+  }
+}
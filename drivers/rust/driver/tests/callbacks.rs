@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use expectest::prelude::*;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+use serde_json::{json, Value};
+
+use tonic::{Code, Request};
+use tonic::transport::Channel;
+
+use pact_plugin_driver::callbacks::{
+  CALLBACK_TOKEN_METADATA_KEY,
+  CallbackHandler,
+  callback_service,
+  set_callback_handler
+};
+use pact_plugin_driver::content::ContentMismatch;
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::proto::pact_plugin_host_client::PactPluginHostClient;
+use pact_plugin_driver::utils::{proto_struct_to_map, to_proto_struct, to_proto_value};
+
+use crate::common::json_body;
+
+mod common;
+
+struct TestHandler;
+
+impl CallbackHandler for TestHandler {
+  fn match_value(&self, rule: &MatchingRule, expected: &Value, actual: &Value, path: &str) -> anyhow::Result<Vec<String>> {
+    if expected == actual {
+      Ok(vec![])
+    } else {
+      Ok(vec![format!("{}: {} did not match {} at {}", rule.name(), actual, expected, path)])
+    }
+  }
+
+  fn match_contents(
+    &self,
+    expected: &OptionalBody,
+    actual: &OptionalBody,
+    _rules: Option<&MatchingRuleCategory>,
+    _allow_unexpected_keys: bool
+  ) -> anyhow::Result<HashMap<String, Vec<ContentMismatch>>> {
+    if expected.value() == actual.value() {
+      Ok(hashmap!{})
+    } else {
+      Ok(hashmap!{
+        "$".to_string() => vec![ContentMismatch {
+          expected: expected.value().unwrap_or_default(),
+          actual: actual.value().unwrap_or_default(),
+          mismatch: "Bodies are different".to_string(),
+          path: "$".to_string(),
+          diff: None
+        }]
+      })
+    }
+  }
+
+  fn provider_state_values(&self, interaction_key: &str) -> HashMap<String, Value> {
+    hashmap!{ "interaction".to_string() => json!(interaction_key) }
+  }
+}
+
+fn with_token<T>(token: &str, message: T) -> Request<T> {
+  let mut request = Request::new(message);
+  request.metadata_mut().insert(CALLBACK_TOKEN_METADATA_KEY, token.parse().unwrap());
+  request
+}
+
+// The handler is global, so the callbacks are all tested against the same running service
+#[tokio::test]
+async fn plugins_can_call_back_into_the_host() {
+  expect!(callback_service().unwrap()).to(be_none());
+
+  set_callback_handler(Arc::new(TestHandler));
+  let details = callback_service().unwrap().unwrap();
+  expect!(callback_service().unwrap()).to(be_some().value(details.clone()));
+  let channel = Channel::from_shared(format!("http://{}", details.address)).unwrap().connect().await.unwrap();
+
+  let mut unauthenticated_client = PactPluginHostClient::new(channel.clone());
+  let result = unauthenticated_client.get_catalogue_entries(CatalogueQuery::default()).await;
+  expect!(result.unwrap_err().code()).to(be_equal_to(Code::Unauthenticated));
+  let result = unauthenticated_client.get_catalogue_entries(with_token("invalid", CatalogueQuery::default())).await;
+  expect!(result.unwrap_err().code()).to(be_equal_to(Code::Unauthenticated));
+
+  let mut client = PactPluginHostClient::new(channel);
+  let token = details.token.as_str();
+
+  let query = CatalogueQuery { r#type: "matcher".to_string() };
+  let entries = client.get_catalogue_entries(with_token(token, query)).await.unwrap().into_inner().entries;
+  expect!(entries.is_empty()).to(be_false());
+  expect!(entries.iter().all(|entry| entry.r#type == catalogue_entry::EntryType::Matcher as i32)).to(be_true());
+  expect!(entries.iter().any(|entry| entry.key == "core/matcher/v2-regex")).to(be_true());
+
+  let response = client.match_value(with_token(token, MatchValueRequest {
+    name: "regex".to_string(),
+    values: Some(to_proto_struct(hashmap!{ "regex".to_string() => json!("\\d+") })),
+    expected: Some(to_proto_value(&json!("100"))),
+    actual: Some(to_proto_value(&json!("200"))),
+    path: "$.id".to_string(),
+    plugin_configuration: None
+  })).await.unwrap().into_inner();
+  expect!(response.error.as_str()).to(be_equal_to(""));
+  expect!(response.mismatches).to(be_equal_to(vec!["regex: \"200\" did not match \"100\" at $.id".to_string()]));
+
+  let response = client.match_value(with_token(token, MatchValueRequest {
+    name: "v2-regex".to_string(),
+    values: Some(to_proto_struct(hashmap!{ "regex".to_string() => json!("\\d+") })),
+    expected: Some(to_proto_value(&json!("100"))),
+    actual: Some(to_proto_value(&json!("200"))),
+    path: "$.id".to_string(),
+    plugin_configuration: None
+  })).await.unwrap().into_inner();
+  expect!(response.error.as_str()).to(be_equal_to(""));
+  expect!(response.mismatches).to(be_equal_to(vec!["regex: \"200\" did not match \"100\" at $.id".to_string()]));
+
+  let response = client.match_value(with_token(token, MatchValueRequest {
+    name: "not-a-rule".to_string(),
+    .. MatchValueRequest::default()
+  })).await.unwrap().into_inner();
+  expect!(response.error.as_str()).to(be_equal_to("'not-a-rule' is not a known matching rule"));
+
+  let response = client.compare_contents(with_token(token, CompareContentsRequest {
+    expected: json_body("{\"a\": 1}"),
+    actual: json_body("{\"a\": 2}"),
+    .. CompareContentsRequest::default()
+  })).await.unwrap().into_inner();
+  expect!(response.error.as_str()).to(be_equal_to(""));
+  expect!(response.results.get("$").map(|m| m.mismatches.len())).to(be_some().value(1));
+
+  let response = client.get_provider_state_values(with_token(token, ProviderStateValuesRequest {
+    interaction_key: "abc123".to_string()
+  })).await.unwrap().into_inner();
+  expect!(proto_struct_to_map(&response.values.unwrap())).to(be_equal_to(hashmap!{
+    "interaction".to_string() => json!("abc123")
+  }));
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use expectest::prelude::*;
+
+use pact_plugin_driver::cancellation::{Cancelled, CancellationToken, with_cancellation};
+
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+  fn drop(&mut self) {
+    self.0.store(true, Ordering::SeqCst);
+  }
+}
+
+#[tokio::test]
+async fn with_cancellation_runs_the_future_to_completion_if_not_cancelled() {
+  let token = CancellationToken::new();
+  expect!(with_cancellation(Some(&token), async { 100 }).await).to(be_ok().value(100));
+  expect!(with_cancellation(None, async { 200 }).await).to(be_ok().value(200));
+}
+
+#[tokio::test]
+async fn with_cancellation_aborts_the_future_when_the_token_is_cancelled() {
+  let token = CancellationToken::new();
+  let dropped = Arc::new(AtomicBool::new(false));
+  let flag = DropFlag(dropped.clone());
+  let call = async move {
+    let _flag = flag;
+    std::future::pending::<u32>().await
+  };
+
+  let child = token.child_token();
+  token.cancel();
+  let result = with_cancellation(Some(&child), call).await;
+
+  expect!(result).to(be_err().value(Cancelled));
+  expect!(dropped.load(Ordering::SeqCst)).to(be_true());
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use expectest::prelude::*;
+
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::{ContentType, JSON, XML};
+use pact_models::generators::GeneratorTestMode;
+use pact_models::matchingrules::{Category, MatchingRuleCategory};
+use pact_plugin_driver::catalogue_manager::{
+  CatalogueEvent,
+  CatalogueEntryProviderType,
+  CatalogueEntryType,
+  find_content_generator,
+  find_content_matcher,
+  find_content_matcher_for_plugins,
+  find_generator,
+  find_interaction_type,
+  find_matcher,
+  lookup_entry,
+  lookup_entry_for_version,
+  register_plugin_entries,
+  remove_plugin_entries,
+  subscribe_to_catalogue_changes
+};
+use pact_plugin_driver::content::{ContentComparison, ContentMatchError, ContentMetadata, GenerateContentOptions};
+use pact_plugin_driver::interactions::configure_interaction_type;
+use pact_plugin_driver::plugin_models::{PactPluginManifest, PluginDependency, PluginDependencyType};
+use pact_plugin_driver::proto::CatalogueEntry as ProtoCatalogueEntry;
+use pact_plugin_driver::proto::catalogue_entry::EntryType;
+
+#[test]
+fn core_entries_are_registered_by_default() {
+  let http = lookup_entry("core/interaction/http");
+  expect!(http.as_ref()).to(be_some());
+  expect!(http.unwrap().provider_type).to(be_equal_to(CatalogueEntryProviderType::CORE));
+
+  let regex = lookup_entry("core/matcher/v2-regex").unwrap();
+  expect!(regex.entry_type).to(be_equal_to(CatalogueEntryType::MATCHER));
+  expect!(regex.values.get("matching-rule").cloned()).to(be_some().value("regex"));
+
+  expect!(lookup_entry("core/content-generator/json")).to(be_some());
+  let generator = lookup_entry("core/generator/ProviderStateGenerator").unwrap();
+  expect!(generator.entry_type).to(be_equal_to(CatalogueEntryType::GENERATOR));
+
+  let matcher = find_content_matcher(&JSON).unwrap();
+  expect!(matcher.is_core()).to(be_true());
+  expect!(matcher.catalogue_entry_key()).to(be_equal_to("core/content-matcher/json"));
+  expect!(find_content_matcher(&XML).map(|m| m.catalogue_entry.key)).to(be_some().value("xml"));
+}
+
+#[tokio::test]
+async fn core_entries_return_an_error_instead_of_calling_a_plugin() {
+  let matcher = find_content_matcher(&JSON).unwrap();
+  let comparison = ContentComparison {
+    id: String::default(),
+    expected: OptionalBody::Null,
+    actual: OptionalBody::Null,
+    context: MatchingRuleCategory::empty(Category::BODY),
+    metadata: ContentMetadata::default(),
+    allow_unexpected_keys: false,
+    plugin_config: None
+  };
+  let result = matcher.match_contents(&comparison, None).await;
+  expect!(matches!(result, Err(ContentMatchError::CoreMatcher(key)) if key == "core/content-matcher/json")).to(be_true());
+
+  let generator = find_content_generator(&JSON).unwrap();
+  let options = GenerateContentOptions {
+    plugin_config: None,
+    test_mode: GeneratorTestMode::Consumer,
+    context: HashMap::new()
+  };
+  let result = generator.generate_content(&JSON, &HashMap::new(), &OptionalBody::Null, &options, None).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to(
+    "Content generator 'core/content-generator/json' is provided by the core framework, not a plugin"));
+
+  let interaction_type = lookup_entry("core/interaction/http").unwrap();
+  let result = configure_interaction_type(&interaction_type, None, HashMap::new(), None).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to(
+    "Interaction type 'core/interaction/http' is provided by the core framework, not a plugin"));
+}
+
+#[test]
+fn catalogue_changes_are_published_to_subscribers() {
+  let mut receiver = subscribe_to_catalogue_changes();
+  let manifest = PactPluginManifest {
+    name: "events".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+
+  register_plugin_entries(&manifest, &vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::ContentMatcher as i32,
+      key: "events".to_string(),
+      values: Default::default()
+    }
+  ]);
+  remove_plugin_entries(&manifest.name, &manifest.version);
+
+  let events: Vec<CatalogueEvent> = std::iter::from_fn(|| receiver.try_recv().ok())
+    .filter(|event| match event {
+      CatalogueEvent::Added(entry) | CatalogueEvent::Removed(entry) => entry.key.starts_with("plugin/events/")
+    })
+    .collect();
+  expect!(events.len()).to(be_equal_to(2));
+  expect!(matches!(&events[0], CatalogueEvent::Added(entry) if entry.key == "plugin/events/content-matcher/events")).to(be_true());
+  expect!(matches!(&events[1], CatalogueEvent::Removed(entry) if entry.key == "plugin/events/content-matcher/events")).to(be_true());
+}
+
+#[test]
+fn entries_for_different_plugin_versions_can_coexist() {
+  let entries = vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::ContentMatcher as i32,
+      key: "versions".to_string(),
+      values: maplit::hashmap!{ "content-types".to_string() => "application/x-versions".to_string() }
+    }
+  ];
+  let v1 = PactPluginManifest {
+    name: "versions".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  let v2 = PactPluginManifest { version: "0.0.2".to_string(), .. v1.clone() };
+  register_plugin_entries(&v1, &entries);
+  register_plugin_entries(&v2, &entries);
+
+  let key = "plugin/versions/content-matcher/versions";
+  expect!(lookup_entry(key).and_then(|e| e.plugin_version())).to(be_some().value("0.0.2"));
+  expect!(lookup_entry_for_version(key, "0.0.1").and_then(|e| e.plugin_version())).to(be_some().value("0.0.1"));
+
+  let content_type = ContentType::parse("application/x-versions").unwrap();
+  expect!(find_content_matcher(&content_type).map(|m| m.plugin_version())).to(be_some().value("0.0.2"));
+  let requested = PluginDependency {
+    name: "versions".to_string(),
+    version: Some("0.0.1".to_string()),
+    dependency_type: PluginDependencyType::Plugin
+  };
+  expect!(find_content_matcher_for_plugins(&content_type, &[requested]).map(|m| m.plugin_version()))
+    .to(be_some().value("0.0.1"));
+
+  remove_plugin_entries("versions", "0.0.2");
+  expect!(lookup_entry(key).and_then(|e| e.plugin_version())).to(be_some().value("0.0.1"));
+  expect!(lookup_entry_for_version(key, "0.0.2")).to(be_none());
+  remove_plugin_entries("versions", "0.0.1");
+  expect!(lookup_entry(key)).to(be_none());
+}
+
+#[test]
+fn latest_plugin_version_is_compared_as_a_semantic_version() {
+  let entries = vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::ContentMatcher as i32,
+      key: "semver".to_string(),
+      values: maplit::hashmap!{ "content-types".to_string() => "application/x-semver".to_string() }
+    },
+    ProtoCatalogueEntry {
+      r#type: EntryType::Matcher as i32,
+      key: "semver-rule".to_string(),
+      values: Default::default()
+    }
+  ];
+  let v9 = PactPluginManifest {
+    name: "semver".to_string(),
+    version: "0.0.9".to_string(),
+    .. PactPluginManifest::default()
+  };
+  let v10 = PactPluginManifest { version: "0.0.10".to_string(), .. v9.clone() };
+  register_plugin_entries(&v10, &entries);
+  register_plugin_entries(&v9, &entries);
+
+  expect!(lookup_entry("plugin/semver/content-matcher/semver").and_then(|e| e.plugin_version()))
+    .to(be_some().value("0.0.10"));
+  let content_type = ContentType::parse("application/x-semver").unwrap();
+  expect!(find_content_matcher(&content_type).map(|m| m.plugin_version())).to(be_some().value("0.0.10"));
+  expect!(find_matcher("semver-rule").map(|m| m.plugin_version())).to(be_some().value("0.0.10"));
+
+  remove_plugin_entries("semver", "0.0.9");
+  remove_plugin_entries("semver", "0.0.10");
+}
+
+#[test]
+fn find_matcher_test() {
+  let manifest = PactPluginManifest {
+    name: "matchers".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  register_plugin_entries(&manifest, &vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::Matcher as i32,
+      key: "matchers-length".to_string(),
+      values: Default::default()
+    },
+    ProtoCatalogueEntry {
+      r#type: EntryType::Matcher as i32,
+      key: "regex".to_string(),
+      values: Default::default()
+    }
+  ]);
+
+  let matcher = find_matcher("matchers-length").unwrap();
+  expect!(matcher.is_core()).to(be_false());
+  expect!(matcher.name()).to(be_equal_to("matchers-length"));
+  expect!(matcher.plugin_name()).to(be_equal_to("matchers"));
+
+  expect!(find_matcher("regex").map(|m| m.is_core())).to(be_some().value(true));
+  expect!(find_matcher("v2-regex").map(|m| m.is_core())).to(be_some().value(true));
+  expect!(find_matcher("unknown")).to(be_none());
+
+  remove_plugin_entries("matchers", "0.0.1");
+}
+
+#[test]
+fn find_generator_test() {
+  let manifest = PactPluginManifest {
+    name: "generators".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  register_plugin_entries(&manifest, &vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::Generator as i32,
+      key: "fake-iban".to_string(),
+      values: Default::default()
+    }
+  ]);
+
+  let generator = find_generator("fake-iban").unwrap();
+  expect!(generator.is_core()).to(be_false());
+  expect!(generator.name()).to(be_equal_to("fake-iban"));
+  expect!(find_generator("RandomInt").map(|g| g.is_core())).to(be_some().value(true));
+  expect!(find_generator("unknown")).to(be_none());
+
+  remove_plugin_entries("generators", "0.0.1");
+}
+
+#[test]
+fn find_interaction_type_test() {
+  let manifest = PactPluginManifest {
+    name: "kafka".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  register_plugin_entries(&manifest, &vec![
+    ProtoCatalogueEntry {
+      r#type: EntryType::Interaction as i32,
+      key: "produce".to_string(),
+      values: Default::default()
+    }
+  ]);
+
+  let entry = find_interaction_type("produce").unwrap();
+  expect!(entry.key.as_str()).to(be_equal_to("plugin/kafka/interaction/produce"));
+  expect!(entry.plugin.map(|p| p.name)).to(be_some().value("kafka"));
+  expect!(find_interaction_type("plugin/kafka/interaction/produce")).to(be_some());
+  expect!(find_interaction_type("http")).to(be_none());
+  expect!(find_interaction_type("consume")).to(be_none());
+
+  remove_plugin_entries("kafka", "0.0.1");
+}
@@ -0,0 +1,155 @@
+//! Mock plugin and fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use maplit::hashmap;
+use pact_models::prelude::{Consumer, Provider};
+use pact_models::v4::pact::V4Pact;
+
+use pact_plugin_driver::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType, CatalogueEntryType};
+use pact_plugin_driver::plugin_models::{PactPluginManifest, PactPluginRpc};
+use pact_plugin_driver::proto::*;
+
+/// Plugin RPC stub that records the last request for each call and returns the configured responses
+#[derive(Default)]
+pub struct MockPlugin {
+  pub capabilities: Option<Vec<String>>,
+  pub configure_interaction_request: Mutex<Option<ConfigureInteractionRequest>>,
+  pub configure_interaction_response: ConfigureInteractionResponse,
+  pub generate_content_request: Mutex<Option<GenerateContentRequest>>,
+  pub compare_contents_calls: Mutex<usize>,
+  pub compare_contents_request: Mutex<Option<CompareContentsRequest>>,
+  pub compare_contents_response: CompareContentsResponse,
+  pub compare_stream_request: Mutex<Option<CompareStreamRequest>>,
+  pub compare_stream_response: CompareStreamResponse,
+  pub start_mock_server_request: Mutex<Option<StartMockServerRequest>>,
+  pub start_mock_server_response: StartMockServerResponse,
+  pub mock_server_results: MockServerResults,
+  pub shutdown_mock_server_response: ShutdownMockServerResponse,
+  pub prepare_request: Mutex<Option<VerificationPreparationRequest>>,
+  pub prepare_response: VerificationPreparationResponse,
+  pub verify_request: Mutex<Option<VerifyInteractionRequest>>,
+  pub verify_response: VerifyInteractionResponse
+}
+
+#[async_trait]
+impl PactPluginRpc for MockPlugin {
+  fn capabilities(&self) -> Option<Vec<String>> {
+    self.capabilities.clone()
+  }
+
+  async fn init_plugin(&self, _request: InitPluginRequest) -> anyhow::Result<InitPluginResponse> {
+    todo!()
+  }
+
+  async fn compare_contents(&self, request: CompareContentsRequest) -> anyhow::Result<CompareContentsResponse> {
+    *self.compare_contents_calls.lock().unwrap() += 1;
+    *self.compare_contents_request.lock().unwrap() = Some(request);
+    Ok(self.compare_contents_response.clone())
+  }
+
+  // Returns an error result for each comparison, except for any with the ID "missing"
+  async fn compare_contents_batch(&self, request: CompareContentsBatchRequest) -> anyhow::Result<CompareContentsBatchResponse> {
+    Ok(CompareContentsBatchResponse {
+      results: request.comparisons.iter()
+        .filter(|comparison| comparison.id != "missing")
+        .map(|comparison| compare_contents_batch_response::ComparisonResult {
+          id: comparison.id.clone(),
+          response: Some(CompareContentsResponse {
+            error: format!("error for {}", comparison.id),
+            .. CompareContentsResponse::default()
+          })
+        }).collect()
+    })
+  }
+
+  async fn compare_stream(&self, request: CompareStreamRequest) -> anyhow::Result<CompareStreamResponse> {
+    *self.compare_stream_request.lock().unwrap() = Some(request);
+    Ok(self.compare_stream_response.clone())
+  }
+
+  async fn configure_interaction(&self, request: ConfigureInteractionRequest) -> anyhow::Result<ConfigureInteractionResponse> {
+    *self.configure_interaction_request.lock().unwrap() = Some(request);
+    Ok(self.configure_interaction_response.clone())
+  }
+
+  async fn generate_content(&self, request: GenerateContentRequest) -> anyhow::Result<GenerateContentResponse> {
+    let contents = request.contents.clone();
+    *self.generate_content_request.lock().unwrap() = Some(request);
+    Ok(GenerateContentResponse { contents })
+  }
+
+  async fn start_mock_server(&self, request: StartMockServerRequest) -> anyhow::Result<StartMockServerResponse> {
+    *self.start_mock_server_request.lock().unwrap() = Some(request);
+    Ok(self.start_mock_server_response.clone())
+  }
+
+  async fn shutdown_mock_server(&self, _request: ShutdownMockServerRequest) -> anyhow::Result<ShutdownMockServerResponse> {
+    Ok(self.shutdown_mock_server_response.clone())
+  }
+
+  async fn get_mock_server_results(&self, _request: MockServerRequest) -> anyhow::Result<MockServerResults> {
+    Ok(self.mock_server_results.clone())
+  }
+
+  async fn prepare_interaction_for_verification(&self, request: VerificationPreparationRequest) -> anyhow::Result<VerificationPreparationResponse> {
+    *self.prepare_request.lock().unwrap() = Some(request);
+    Ok(self.prepare_response.clone())
+  }
+
+  async fn verify_interaction(&self, request: VerifyInteractionRequest) -> anyhow::Result<VerifyInteractionResponse> {
+    *self.verify_request.lock().unwrap() = Some(request);
+    Ok(self.verify_response.clone())
+  }
+}
+
+/// Manifest for the mock plugin
+pub fn manifest() -> PactPluginManifest {
+  PactPluginManifest {
+    name: "mock".to_string(),
+    version: "1.2.3".to_string(),
+    .. PactPluginManifest::default()
+  }
+}
+
+/// Catalogue entry provided by the mock plugin
+pub fn plugin_entry(entry_type: CatalogueEntryType, key: &str) -> CatalogueEntry {
+  CatalogueEntry {
+    entry_type,
+    provider_type: CatalogueEntryProviderType::PLUGIN,
+    plugin: Some(manifest()),
+    key: key.to_string(),
+    values: hashmap!{}
+  }
+}
+
+/// Content matcher entry provided by the mock plugin
+pub fn content_matcher_entry() -> CatalogueEntry {
+  plugin_entry(CatalogueEntryType::CONTENT_MATCHER, "plugin/mock/content-matcher/mock")
+}
+
+/// Mock server (and transport) entry provided by the mock plugin
+pub fn mock_server_entry() -> CatalogueEntry {
+  plugin_entry(CatalogueEntryType::MOCK_SERVER, "plugin/mock/mock-server/grpc")
+}
+
+/// Empty V4 Pact between a consumer and provider
+pub fn pact() -> V4Pact {
+  V4Pact {
+    consumer: Consumer { name: "consumer".to_string() },
+    provider: Provider { name: "provider".to_string() },
+    .. V4Pact::default()
+  }
+}
+
+/// JSON body message
+pub fn json_body(json: &str) -> Option<Body> {
+  Some(Body {
+    content_type: "application/json".to_string(),
+    content: Some(json.as_bytes().to_vec()),
+    content_type_hint: 0
+  })
+}
@@ -0,0 +1,504 @@
+use bytes::Bytes;
+use expectest::prelude::*;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::ContentTypeHint;
+use pact_models::matchingrules::{Category, MatchingRuleCategory};
+use pact_models::generators::GeneratorTestMode;
+use pact_models::prelude::ContentType;
+use serde_json::json;
+
+use pact_plugin_driver::catalogue_manager::{CatalogueEntry, CatalogueEntryType};
+use pact_plugin_driver::content::{
+  ContentComparison,
+  ContentGenerator,
+  ContentMatchError,
+  ContentMatcher,
+  ContentMetadata,
+  ContentMismatch,
+  GenerateContentOptions,
+  InteractionContents,
+  MessageDirection,
+  StreamCount,
+  StreamMessage,
+  display_bytes
+};
+use pact_plugin_driver::interactions::configure_interaction_type_with_plugin;
+use pact_plugin_driver::plugin_models::PluginInteractionConfig;
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::utils::to_proto_struct;
+
+use crate::common::{MockPlugin, content_matcher_entry, plugin_entry};
+
+mod common;
+
+fn content_matcher() -> ContentMatcher {
+  ContentMatcher { catalogue_entry: content_matcher_entry() }
+}
+
+fn matching_rule(rule_type: &str, values: serde_json::Value) -> MatchingRules {
+  MatchingRules {
+    rule: vec![
+      MatchingRule {
+        r#type: rule_type.to_string(),
+        values: values.as_object().map(|o| to_proto_struct(o.iter().map(|(k, v)| (k.clone(), v.clone())).collect()))
+      }
+    ]
+  }
+}
+
+#[test]
+fn display_bytes_renders_utf8_as_text_and_binary_as_hex() {
+  expect!(display_bytes("some text".as_bytes())).to(be_equal_to("some text"));
+  expect!(display_bytes(&[])).to(be_equal_to(""));
+  expect!(display_bytes(&[0x0a, 0x00, 0xff, 0xfe])).to(be_equal_to("0x0a00fffe"));
+
+  let mismatch = ContentMismatch {
+    expected: Bytes::from_static(&[0x08, 0x96, 0x01]),
+    actual: Bytes::from("150"),
+    mismatch: "Expected 150 but got something else".to_string(),
+    path: "$.id".to_string(),
+    diff: None
+  };
+  expect!(mismatch.expected_display()).to(be_equal_to("0x089601"));
+  expect!(mismatch.actual_display()).to(be_equal_to("150"));
+}
+
+#[tokio::test]
+async fn configure_interaction_converts_the_rules_and_generators() {
+  let plugin = MockPlugin {
+    configure_interaction_response: ConfigureInteractionResponse {
+      interaction: vec![
+        InteractionResponse {
+          rules: hashmap!{
+            "column:1".to_string() => matching_rule("type", json!({})),
+            "column:2".to_string() => matching_rule("regex", json!({ "regex": "\\d+" }))
+          },
+          generators: hashmap!{
+            "column:2".to_string() => Generator {
+              r#type: "RandomInt".to_string(),
+              values: Some(to_proto_struct(hashmap!{ "min".to_string() => json!(1), "max".to_string() => json!(10) }))
+            }
+          },
+          .. InteractionResponse::default()
+        }
+      ],
+      .. ConfigureInteractionResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+
+  let result = content_matcher()
+    .configure_interaction_with_plugin(&plugin, &ContentType::from("text/csv"), hashmap!{}).await;
+
+  let (contents, _) = result.unwrap();
+  expect!(contents.len()).to(be_equal_to(1));
+  expect!(contents[0].rules.as_ref().map(|r| r.rules.len())).to(be_some().value(2));
+  expect!(contents[0].generators.as_ref().map(|g| g.categories.len())).to(be_some().value(1));
+}
+
+#[tokio::test]
+async fn configure_interaction_returns_an_error_with_all_invalid_rules_and_generators() {
+  let plugin = MockPlugin {
+    configure_interaction_response: ConfigureInteractionResponse {
+      interaction: vec![
+        InteractionResponse {
+          rules: hashmap!{
+            "$.[".to_string() => matching_rule("type", json!({})),
+            "$.a".to_string() => matching_rule("not-a-rule", json!({})),
+            "$.b".to_string() => matching_rule("regex", json!({}))
+          },
+          generators: hashmap!{
+            "$.c".to_string() => Generator {
+              r#type: "NotAGenerator".to_string(),
+              values: None
+            }
+          },
+          part_name: "request".to_string(),
+          .. InteractionResponse::default()
+        }
+      ],
+      .. ConfigureInteractionResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+
+  let result = content_matcher()
+    .configure_interaction_with_plugin(&plugin, &ContentType::from("text/csv"), hashmap!{}).await;
+
+  let error = result.unwrap_err().to_string();
+  expect!(error.starts_with("Plugin mock/1.2.3 returned invalid matching rules or generators")).to(be_true());
+  expect!(error.contains("Matching rule path '$.[' is not valid")).to(be_true());
+  expect!(error.contains("Matching rule 'not-a-rule' for path '$.a' is not valid")).to(be_true());
+  expect!(error.contains("Matching rule 'regex' for path '$.b' is not valid")).to(be_true());
+  expect!(error.contains("Generator 'NotAGenerator' for path '$.c' is not valid")).to(be_true());
+  expect!(error.contains("(part 'request')")).to(be_true());
+}
+
+#[tokio::test]
+async fn configure_interaction_type_sends_the_interaction_type_and_returns_the_interaction_fields() {
+  let plugin = MockPlugin {
+    configure_interaction_response: ConfigureInteractionResponse {
+      interaction: vec![
+        InteractionResponse {
+          interaction_fields: Some(to_proto_struct(hashmap!{ "topic".to_string() => json!("orders") })),
+          .. InteractionResponse::default()
+        }
+      ],
+      .. ConfigureInteractionResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+  let interaction_type = plugin_entry(CatalogueEntryType::INTERACTION, "plugin/mock/interaction/produce");
+
+  let (contents, _) = configure_interaction_type_with_plugin(&plugin, &interaction_type, None,
+    hashmap!{ "topic".to_string() => json!("orders") }).await.unwrap();
+
+  expect!(contents[0].interaction_fields.clone()).to(be_equal_to(hashmap!{ "topic".to_string() => json!("orders") }));
+  let request = plugin.configure_interaction_request.lock().unwrap().clone().unwrap();
+  expect!(request.interaction_type).to(be_equal_to("produce"));
+  expect!(request.content_type).to(be_equal_to(""));
+}
+
+#[tokio::test]
+async fn generate_content_passes_the_plugin_configuration_test_mode_and_context_to_the_plugin() {
+  let plugin = MockPlugin::default();
+  let generator = ContentGenerator {
+    catalogue_entry: CatalogueEntry {
+      entry_type: CatalogueEntryType::CONTENT_GENERATOR,
+      .. content_matcher().catalogue_entry
+    }
+  };
+  let config = PluginInteractionConfig {
+    pact_configuration: hashmap!{ "pact".to_string() => json!("config") },
+    interaction_configuration: hashmap!{ "csvHeaders".to_string() => json!(false) }
+  };
+  let body = OptionalBody::Present(Bytes::from("1,2\n3,4\n"), Some(ContentType::from("text/csv")), None);
+
+  let options = GenerateContentOptions {
+    plugin_config: Some(config),
+    test_mode: GeneratorTestMode::Provider,
+    context: hashmap!{ "id" => json!(100) }
+  };
+
+  let result = generator.generate_content_with_plugin(&plugin, &ContentType::from("text/csv"),
+    &hashmap!{}, &body, &options).await;
+
+  expect!(result.unwrap().value()).to(be_some().value(Bytes::from("1,2\n3,4\n")));
+  let request = plugin.generate_content_request.lock().unwrap().clone().unwrap();
+  let plugin_configuration = request.plugin_configuration.unwrap();
+  expect!(plugin_configuration.interaction_configuration.unwrap().fields.get("csvHeaders").cloned())
+    .to(be_some().value(prost_types::Value { kind: Some(prost_types::value::Kind::BoolValue(false)) }));
+  expect!(plugin_configuration.pact_configuration.unwrap().fields.contains_key("pact")).to(be_true());
+  expect!(request.test_mode).to(be_equal_to(generate_content_request::TestMode::Provider as i32));
+  expect!(request.test_context.unwrap().fields.get("id").cloned())
+    .to(be_some().value(prost_types::Value { kind: Some(prost_types::value::Kind::NumberValue(100.0)) }));
+}
+
+#[tokio::test]
+async fn generate_content_passes_the_content_type_hint_in_both_directions() {
+  let plugin = MockPlugin::default();
+  let generator = ContentGenerator {
+    catalogue_entry: CatalogueEntry {
+      entry_type: CatalogueEntryType::CONTENT_GENERATOR,
+      .. content_matcher().catalogue_entry
+    }
+  };
+  let content_type = ContentType::from("application/octet-stream");
+  let body = OptionalBody::Present(Bytes::from("1,2\n"), Some(content_type.clone()), Some(ContentTypeHint::TEXT));
+
+  let options = GenerateContentOptions {
+    plugin_config: None,
+    test_mode: GeneratorTestMode::Consumer,
+    context: hashmap!{}
+  };
+
+  let result = generator.generate_content_with_plugin(&plugin, &content_type, &hashmap!{}, &body, &options)
+    .await.unwrap();
+
+  let request = plugin.generate_content_request.lock().unwrap().clone().unwrap();
+  expect!(request.contents.unwrap().content_type_hint).to(be_equal_to(body::ContentTypeHint::Text as i32));
+  expect!(matches!(result, OptionalBody::Present(_, _, Some(ContentTypeHint::TEXT)))).to(be_true());
+}
+
+fn comparison(id: &str) -> ContentComparison {
+  ContentComparison {
+    id: id.to_string(),
+    expected: OptionalBody::Present(Bytes::from("a,b"), Some(ContentType::from("text/csv")), None),
+    actual: OptionalBody::Present(Bytes::from("a,c"), Some(ContentType::from("text/csv")), None),
+    context: MatchingRuleCategory::empty(Category::BODY),
+    metadata: ContentMetadata::default(),
+    allow_unexpected_keys: false,
+    plugin_config: None
+  }
+}
+
+#[tokio::test]
+async fn match_contents_batch_sends_one_request_if_the_plugin_supports_it() {
+  let plugin = MockPlugin::default();
+  let mut matcher = content_matcher();
+  matcher.catalogue_entry.values.insert("batch-compare".to_string(), "true".to_string());
+
+  let results = matcher.match_contents_batch_with_plugin(&plugin, &[comparison("one"), comparison("missing")]).await;
+
+  expect!(*plugin.compare_contents_calls.lock().unwrap()).to(be_equal_to(0));
+  expect!(results.len()).to(be_equal_to(2));
+  expect!(results[0].0.as_str()).to(be_equal_to("one"));
+  expect!(matches!(&results[0].1, Err(ContentMatchError::PluginError(err)) if err == "error for one")).to(be_true());
+  expect!(results[1].0.as_str()).to(be_equal_to("missing"));
+  expect!(matches!(&results[1].1, Err(ContentMatchError::PluginError(err))
+    if err == "Plugin did not return a result for comparison 'missing'")).to(be_true());
+}
+
+#[tokio::test]
+async fn match_contents_batch_falls_back_to_a_request_per_comparison() {
+  let plugin = MockPlugin::default();
+  let matcher = content_matcher();
+
+  let results = matcher.match_contents_batch_with_plugin(&plugin, &[comparison("one"), comparison("two")]).await;
+
+  expect!(*plugin.compare_contents_calls.lock().unwrap()).to(be_equal_to(2));
+  expect!(results.iter().map(|(id, result)| (id.as_str(), result.is_ok())).collect::<Vec<_>>())
+    .to(be_equal_to(vec![("one", true), ("two", true)]));
+}
+
+#[tokio::test]
+async fn match_contents_batch_falls_back_if_the_plugin_did_not_report_the_batch_capability() {
+  let plugin = MockPlugin {
+    capabilities: Some(vec!["CompareStream".to_string()]),
+    .. MockPlugin::default()
+  };
+  let mut matcher = content_matcher();
+  matcher.catalogue_entry.values.insert("batch-compare".to_string(), "true".to_string());
+
+  let results = matcher.match_contents_batch_with_plugin(&plugin, &[comparison("one"), comparison("two")]).await;
+
+  expect!(*plugin.compare_contents_calls.lock().unwrap()).to(be_equal_to(2));
+  expect!(results.len()).to(be_equal_to(2));
+}
+
+#[tokio::test]
+async fn match_contents_batch_sends_one_request_if_the_plugin_reported_the_batch_capability() {
+  let plugin = MockPlugin {
+    capabilities: Some(vec!["CompareContentsBatch".to_string()]),
+    .. MockPlugin::default()
+  };
+
+  let results = content_matcher().match_contents_batch_with_plugin(&plugin, &[comparison("one"), comparison("two")]).await;
+
+  expect!(*plugin.compare_contents_calls.lock().unwrap()).to(be_equal_to(0));
+  expect!(results.len()).to(be_equal_to(2));
+}
+
+#[tokio::test]
+async fn match_contents_sends_the_metadata_and_returns_metadata_mismatches() {
+  let plugin = MockPlugin {
+    compare_contents_response: CompareContentsResponse {
+      metadata_results: hashmap!{
+        "contentType".to_string() => ContentMismatches {
+          mismatches: vec![
+            pact_plugin_driver::proto::ContentMismatch {
+              expected: Some("Request".as_bytes().to_vec()),
+              actual: Some("Response".as_bytes().to_vec()),
+              mismatch: "Expected message type 'Request' but got 'Response'".to_string(),
+              path: "contentType".to_string(),
+              diff: String::default()
+            }
+          ]
+        }
+      },
+      .. CompareContentsResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+  let mut comparison = comparison("one");
+  comparison.context = MatchingRuleCategory::empty(Category::CONTENTS);
+  comparison.metadata = ContentMetadata {
+    expected_metadata: hashmap!{ "contentType".to_string() => json!("Request") },
+    actual_metadata: hashmap!{ "contentType".to_string() => json!("Response") },
+    expected_headers: hashmap!{ "x-id".to_string() => vec!["1".to_string()] },
+    .. ContentMetadata::default()
+  };
+
+  let results = content_matcher().match_contents_batch_with_plugin(&plugin, &[comparison]).await;
+
+  let request = plugin.compare_contents_request.lock().unwrap().clone().unwrap();
+  expect!(request.expected_metadata.unwrap().fields.contains_key("contentType")).to(be_true());
+  expect!(request.actual_metadata.unwrap().fields.contains_key("contentType")).to(be_true());
+  expect!(request.expected_headers.unwrap().fields.contains_key("x-id")).to(be_true());
+  expect!(request.actual_headers).to(be_none());
+  match &results[0].1 {
+    Err(ContentMatchError::Mismatches(mismatches)) => {
+      expect!(mismatches.keys().cloned().collect::<Vec<_>>()).to(be_equal_to(vec![Category::METADATA]));
+      let mismatch = &mismatches[&Category::METADATA]["contentType"][0];
+      expect!(mismatch.expected_display()).to(be_equal_to("Request"));
+    }
+    result => panic!("Expected metadata mismatches, got {:?}", result)
+  }
+}
+
+#[tokio::test]
+async fn match_contents_adds_a_diff_of_the_bodies_to_the_first_mismatch_if_the_plugin_did_not() {
+  let plugin = MockPlugin {
+    compare_contents_response: CompareContentsResponse {
+      results: hashmap!{
+        "column:2".to_string() => ContentMismatches {
+          mismatches: vec![
+            pact_plugin_driver::proto::ContentMismatch {
+              expected: Some("b".as_bytes().to_vec()),
+              actual: Some("c".as_bytes().to_vec()),
+              mismatch: "Expected 'b' but got 'c'".to_string(),
+              path: "column:2".to_string(),
+              diff: String::default()
+            }
+          ]
+        },
+        "column:1".to_string() => ContentMismatches {
+          mismatches: vec![
+            pact_plugin_driver::proto::ContentMismatch {
+              expected: Some("a".as_bytes().to_vec()),
+              actual: Some("x".as_bytes().to_vec()),
+              mismatch: "Expected 'a' but got 'x'".to_string(),
+              path: "column:1".to_string(),
+              diff: String::default()
+            }
+          ]
+        }
+      },
+      .. CompareContentsResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+  let mut comparison = comparison("one");
+  comparison.actual = OptionalBody::Present(Bytes::from("x,c"), Some(ContentType::from("text/csv")), None);
+
+  let results = content_matcher().match_contents_batch_with_plugin(&plugin, &[comparison]).await;
+
+  match &results[0].1 {
+    Err(ContentMatchError::Mismatches(mismatches)) => {
+      let body = &mismatches[&Category::BODY];
+      expect!(body["column:1"][0].diff.clone()).to(be_some().value(
+        "-row 1: a,b\n+row 1: x,c\n  column 1: 'a' -> 'x'\n  column 2: 'b' -> 'c'"));
+      expect!(body["column:2"][0].diff.clone()).to(be_none());
+    }
+    result => panic!("Expected body mismatches, got {:?}", result)
+  }
+}
+
+#[tokio::test]
+async fn configure_interaction_returns_stream_parts_in_sequence_order() {
+  let plugin = MockPlugin {
+    configure_interaction_response: ConfigureInteractionResponse {
+      interaction: vec![
+        InteractionResponse {
+          part_name: "update".to_string(),
+          direction: interaction_response::MessageDirection::ProviderToConsumer as i32,
+          sequence: 2,
+          count: Some(pact_plugin_driver::proto::StreamCount { min: 1, max: 0 }),
+          .. InteractionResponse::default()
+        },
+        InteractionResponse {
+          part_name: "subscribe".to_string(),
+          direction: interaction_response::MessageDirection::ConsumerToProvider as i32,
+          sequence: 1,
+          .. InteractionResponse::default()
+        }
+      ],
+      .. ConfigureInteractionResponse::default()
+    },
+    .. MockPlugin::default()
+  };
+
+  let (contents, _) = content_matcher()
+    .configure_interaction_with_plugin(&plugin, &ContentType::from("text/csv"), hashmap!{}).await.unwrap();
+
+  expect!(contents.iter().map(|c| c.part_name.as_str()).collect::<Vec<_>>()).to(be_equal_to(vec!["subscribe", "update"]));
+  expect!(contents[0].direction).to(be_some().value(MessageDirection::ConsumerToProvider));
+  expect!(contents[0].count).to(be_none());
+  expect!(contents[1].direction).to(be_some().value(MessageDirection::ProviderToConsumer));
+  expect!(contents[1].count).to(be_some().value(StreamCount { min: 1, max: None }));
+}
+
+#[tokio::test]
+async fn match_stream_sends_the_expected_and_actual_messages_and_returns_the_mismatches() {
+  let plugin = MockPlugin {
+    compare_stream_response: CompareStreamResponse {
+      error: String::default(),
+      mismatches: vec![
+        pact_plugin_driver::proto::StreamMismatch {
+          expected_index: 1,
+          actual_index: -1,
+          mismatch: "Expected at least 2 messages but received 1".to_string(),
+          content_mismatches: vec![]
+        }
+      ]
+    },
+    capabilities: Some(vec!["CompareStream".to_string()]),
+    .. MockPlugin::default()
+  };
+  let expected = vec![
+    InteractionContents {
+      body: OptionalBody::from("a,b"),
+      direction: Some(MessageDirection::ConsumerToProvider),
+      .. InteractionContents::default()
+    },
+    InteractionContents {
+      body: OptionalBody::from("c,d"),
+      direction: Some(MessageDirection::ProviderToConsumer),
+      count: Some(StreamCount { min: 2, max: None }),
+      .. InteractionContents::default()
+    }
+  ];
+  let actual = vec![
+    StreamMessage { contents: OptionalBody::from("a,b"), direction: MessageDirection::ConsumerToProvider },
+    StreamMessage { contents: OptionalBody::from("c,d"), direction: MessageDirection::ProviderToConsumer }
+  ];
+
+  let result = content_matcher().match_stream_with_plugin(&plugin, &expected, &actual, false, None).await;
+
+  match result {
+    Err(ContentMatchError::StreamMismatches(mismatches)) => {
+      expect!(mismatches.len()).to(be_equal_to(1));
+      expect!(mismatches[0].expected_index).to(be_equal_to(1));
+      expect!(mismatches[0].actual_index).to(be_none());
+    }
+    _ => panic!("Expected stream mismatches, got {:?}", result)
+  }
+  let request = plugin.compare_stream_request.lock().unwrap().clone().unwrap();
+  expect!(request.expected.len()).to(be_equal_to(2));
+  expect!(request.expected[0].count.clone()).to(be_none());
+  expect!(request.expected[1].count.clone()).to(be_some().value(pact_plugin_driver::proto::StreamCount { min: 2, max: 0 }));
+  expect!(request.actual[1].direction).to(be_equal_to(interaction_response::MessageDirection::ProviderToConsumer as i32));
+}
+
+#[tokio::test]
+async fn legacy_plugins_without_capabilities_only_support_what_the_catalogue_entry_declares() {
+  let plugin = MockPlugin::default();
+  let expected = vec![InteractionContents { body: OptionalBody::from("a,b"), .. InteractionContents::default() }];
+  let actual = vec![StreamMessage { contents: OptionalBody::from("a,b"), direction: MessageDirection::ConsumerToProvider }];
+
+  let result = content_matcher().match_stream_with_plugin(&plugin, &expected, &actual, false, None).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("Plugin failed to compare the contents - \
+    Plugin for plugin/mock/content-matcher/mock does not support comparing streams of messages"));
+  expect!(plugin.compare_stream_request.lock().unwrap().is_none()).to(be_true());
+
+  let mut matcher = content_matcher();
+  matcher.catalogue_entry.values.insert("capabilities".to_string(), "CompareStream".to_string());
+  let result = matcher.match_stream_with_plugin(&plugin, &expected, &actual, false, None).await;
+  expect!(result).to(be_ok());
+  expect!(plugin.compare_stream_request.lock().unwrap().is_some()).to(be_true());
+}
+
+#[tokio::test]
+async fn match_stream_returns_an_error_if_the_plugin_does_not_support_streams() {
+  let plugin = MockPlugin {
+    capabilities: Some(vec!["CompareContentsBatch".to_string()]),
+    .. MockPlugin::default()
+  };
+
+  let result = content_matcher().match_stream_with_plugin(&plugin, &[], &[], false, None).await;
+
+  expect!(matches!(&result, Err(ContentMatchError::PluginError(err))
+    if err.ends_with("does not support comparing streams of messages"))).to(be_true());
+  expect!(plugin.compare_stream_request.lock().unwrap().is_none()).to(be_true());
+}
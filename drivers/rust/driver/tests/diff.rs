@@ -0,0 +1,33 @@
+use expectest::prelude::*;
+use pact_models::content_types::ContentType;
+
+use pact_plugin_driver::diff::{generate_diff, line_diff, table_diff};
+
+#[test]
+fn line_diff_test() {
+  expect!(line_diff("a\nb\nc", "a\nx\nc")).to(be_equal_to(" a\n-b\n+x\n c"));
+  expect!(line_diff("a", "a\nb")).to(be_equal_to(" a\n+b"));
+}
+
+#[test]
+fn table_diff_test() {
+  let expected = vec![vec!["a", "b", "c"], vec!["d", "e", "f"]];
+  let actual = vec![vec!["a", "b", "c"], vec!["d", "x", "f", "g"], vec!["h", "i", "j"]];
+  expect!(table_diff(&expected, &actual)).to(be_equal_to(
+    "-row 2: d,e,f\n+row 2: d,x,f,g\n  column 2: 'e' -> 'x'\n  column 4: <missing> -> 'g'\n+row 3: h,i,j"));
+  expect!(table_diff(&expected, &expected)).to(be_equal_to(""));
+}
+
+#[test]
+fn generate_diff_test() {
+  let csv = ContentType::from("text/csv");
+  expect!(generate_diff(Some(&csv), b"a,\"b,c\"\n", b"a,\"b,d\"\n")).to(be_some().value(
+    "-row 1: a,b,c\n+row 1: a,b,d\n  column 2: 'b,c' -> 'b,d'"));
+  expect!(generate_diff(Some(&csv), b"a,\"b\nc\"\n", b"a,\"b\nd\"\n")).to(be_some().value(
+    "-row 1: a,b\nc\n+row 1: a,b\nd\n  column 2: 'b\nc' -> 'b\nd'"));
+  expect!(generate_diff(Some(&ContentType::from("text/plain")), b"a\nb", b"a\nc"))
+    .to(be_some().value(" a\n-b\n+c"));
+  expect!(generate_diff(None, b"same", b"same")).to(be_none());
+  expect!(generate_diff(Some(&ContentType::from("image/png")), b"a", b"b")).to(be_none());
+  expect!(generate_diff(None, &[0xff, 0xfe], &[0x00])).to(be_none());
+}
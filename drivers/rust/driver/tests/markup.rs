@@ -0,0 +1,62 @@
+use expectest::prelude::*;
+use pact_models::v4::interaction::InteractionMarkup;
+
+use pact_plugin_driver::content::InteractionContents;
+use pact_plugin_driver::markup::{
+  commonmark_to_html,
+  Markup,
+  MarkupType,
+  render_interaction_markup,
+  sanitise_html
+};
+
+#[test]
+fn markup_type_round_trips_through_the_pact_file_format() {
+  expect!(MarkupType::parse("HTML")).to(be_equal_to(MarkupType::Html));
+  expect!(MarkupType::parse("COMMON_MARK")).to(be_equal_to(MarkupType::CommonMark));
+  expect!(MarkupType::parse("")).to(be_equal_to(MarkupType::CommonMark));
+
+  let markup = Markup { markup: "<b>test</b>".to_string(), markup_type: MarkupType::Html };
+  let pact_markup = InteractionMarkup::from(&markup);
+  expect!(pact_markup.markup_type.as_str()).to(be_equal_to("HTML"));
+  expect!(Markup::from(&pact_markup)).to(be_equal_to(markup));
+}
+
+#[test]
+fn commonmark_to_html_supports_tables() {
+  let html = commonmark_to_html("# Data\n\n|a|b|\n|-|-|\n|1|2|\n");
+  expect!(html.contains("<h1>Data</h1>")).to(be_true());
+  expect!(html.contains("<td>1</td>")).to(be_true());
+}
+
+#[test]
+fn sanitise_html_removes_scripts_and_event_handlers() {
+  expect!(sanitise_html("<p class=\"x\" onclick=\"alert(1)\">test<script>alert(2)</script></p><a href=\"javascript:alert(3)\">link</a>"))
+    .to(be_equal_to("<p class=\"x\">test</p><a rel=\"noopener noreferrer\">link</a>"));
+}
+
+#[test]
+fn render_interaction_markup_combines_the_parts_and_sanitises_them() {
+  let parts = vec![
+    InteractionContents {
+      part_name: "request".to_string(),
+      interaction_markup: "**request** <img src=x onerror=alert(1)>".to_string(),
+      interaction_markup_type: MarkupType::CommonMark,
+      .. InteractionContents::default()
+    },
+    InteractionContents {
+      part_name: "metadata".to_string(),
+      .. InteractionContents::default()
+    },
+    InteractionContents {
+      part_name: "response".to_string(),
+      interaction_markup: "<div><i>response".to_string(),
+      interaction_markup_type: MarkupType::Html,
+      .. InteractionContents::default()
+    }
+  ];
+
+  expect!(render_interaction_markup(&parts)).to(be_equal_to(
+    "<div class=\"interaction-part\">\n<p><strong>request</strong> <img src=\"x\"></p>\n</div>\n\
+    <div class=\"interaction-part\">\n<div><i>response</i></div></div>\n"));
+}
@@ -0,0 +1,236 @@
+use expectest::prelude::*;
+
+use pact_plugin_driver::cancellation::CancellationToken;
+use pact_plugin_driver::catalogue_manager::{
+  CatalogueEntry,
+  CatalogueEntryProviderType,
+  find_mock_server,
+  register_plugin_entries,
+  remove_plugin_entries
+};
+use pact_plugin_driver::mock_server::{
+  MockServerConfig,
+  MockServerDetails,
+  get_mock_server_results_with_plugin,
+  shutdown_mock_server_with_plugin,
+  start_mock_server,
+  start_mock_server_with_plugin
+};
+use pact_plugin_driver::plugin_models::PactPluginManifest;
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::proto::catalogue_entry::EntryType;
+
+use crate::common::{MockPlugin, manifest, mock_server_entry, pact};
+
+mod common;
+
+#[test]
+fn find_mock_server_test() {
+  let manifest = PactPluginManifest {
+    name: "mock-servers".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  register_plugin_entries(&manifest, &vec![
+    pact_plugin_driver::proto::CatalogueEntry {
+      r#type: EntryType::MockServer as i32,
+      key: "tcp".to_string(),
+      values: Default::default()
+    }
+  ]);
+
+  let entry = find_mock_server("tcp").unwrap();
+  expect!(entry.key).to(be_equal_to("plugin/mock-servers/mock-server/tcp"));
+  expect!(entry.plugin.map(|p| p.name)).to(be_some().value("mock-servers"));
+  expect!(find_mock_server("udp")).to(be_none());
+
+  remove_plugin_entries("mock-servers", "0.0.1");
+}
+
+#[tokio::test]
+async fn start_mock_server_sends_the_pact_and_config_to_the_plugin() {
+  let plugin = MockPlugin {
+    start_mock_server_response: StartMockServerResponse {
+      response: Some(start_mock_server_response::Response::Details(pact_plugin_driver::proto::MockServerDetails {
+        key: "1234".to_string(),
+        port: 5678,
+        address: "127.0.0.1".to_string()
+      }))
+    },
+    .. MockPlugin::default()
+  };
+  let config = MockServerConfig {
+    host_interface: Some("0.0.0.0".to_string()),
+    port: 5678,
+    tls: true
+  };
+
+  let details = start_mock_server_with_plugin(&plugin, &mock_server_entry(), &pact(), config, None).await
+    .unwrap();
+
+  expect!(details).to(be_equal_to(MockServerDetails {
+    key: "1234".to_string(),
+    address: "127.0.0.1".to_string(),
+    port: 5678,
+    plugin: manifest()
+  }));
+  let request = plugin.start_mock_server_request.lock().unwrap().clone().unwrap();
+  expect!(request.host_interface).to(be_equal_to("0.0.0.0"));
+  expect!(request.port).to(be_equal_to(5678));
+  expect!(request.tls).to(be_true());
+  let pact_json: serde_json::Value = serde_json::from_str(request.pact.as_str()).unwrap();
+  expect!(pact_json["consumer"]["name"].as_str()).to(be_some().value("consumer"));
+  expect!(pact_json["metadata"]["pactSpecification"]["version"].as_str()).to(be_some().value("4.0"));
+}
+
+#[tokio::test]
+async fn start_mock_server_returns_an_error_if_the_plugin_fails_to_start_it() {
+  let plugin = MockPlugin {
+    start_mock_server_response: StartMockServerResponse {
+      response: Some(start_mock_server_response::Response::Error("port in use".to_string()))
+    },
+    .. MockPlugin::default()
+  };
+
+  let result = start_mock_server_with_plugin(&plugin, &mock_server_entry(), &pact(),
+    MockServerConfig::default(), None).await;
+
+  expect!(result.unwrap_err().to_string())
+    .to(be_equal_to("Plugin mock/1.2.3 failed to start the mock server - port in use"));
+}
+
+#[tokio::test]
+async fn start_mock_server_returns_an_error_if_the_call_is_cancelled() {
+  let plugin = MockPlugin::default();
+  let token = CancellationToken::new();
+  token.cancel();
+
+  let result = start_mock_server_with_plugin(&plugin, &mock_server_entry(), &pact(),
+    MockServerConfig::default(), Some(&token)).await;
+
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("Call to plugin was cancelled"));
+}
+
+#[tokio::test]
+async fn start_mock_server_returns_an_error_if_the_entry_is_not_provided_by_a_plugin() {
+  let entry = CatalogueEntry {
+    provider_type: CatalogueEntryProviderType::CORE,
+    plugin: None,
+    key: "core/mock-server/http".to_string(),
+    .. mock_server_entry()
+  };
+
+  let result = start_mock_server(&entry, &pact(), MockServerConfig::default(), None).await;
+
+  expect!(result.unwrap_err().to_string())
+    .to(be_equal_to("Mock server 'core/mock-server/http' is not provided by a plugin"));
+}
+
+#[tokio::test]
+async fn start_mock_server_returns_an_error_if_the_plugin_does_not_support_mock_servers() {
+  let plugin = MockPlugin {
+    capabilities: Some(vec!["CompareContentsBatch".to_string()]),
+    .. MockPlugin::default()
+  };
+
+  let result = start_mock_server_with_plugin(&plugin, &mock_server_entry(), &pact(),
+    MockServerConfig::default(), None).await;
+
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("Plugin mock/1.2.3 does not support mock servers"));
+  expect!(plugin.start_mock_server_request.lock().unwrap().is_none()).to(be_true());
+}
+
+#[tokio::test]
+async fn get_mock_server_results_converts_the_mismatches() {
+  let plugin = MockPlugin {
+    mock_server_results: MockServerResults {
+      ok: false,
+      results: vec![
+        MockServerResult {
+          interaction_key: "abc".to_string(),
+          path: "/Service/Method".to_string(),
+          error: String::default(),
+          mismatches: vec![
+            ContentMismatch {
+              expected: Some("100".as_bytes().to_vec()),
+              actual: Some("200".as_bytes().to_vec()),
+              mismatch: "Expected 100 but got 200".to_string(),
+              path: "$.id".to_string(),
+              diff: String::default()
+            }
+          ]
+        }
+      ]
+    },
+    .. MockPlugin::default()
+  };
+  let results = get_mock_server_results_with_plugin(&plugin, &mock_server_details()).await.unwrap();
+
+  expect!(results.len()).to(be_equal_to(1));
+  expect!(results[0].path.as_str()).to(be_equal_to("/Service/Method"));
+  expect!(results[0].mismatches[0].expected_display()).to(be_equal_to("100"));
+  expect!(results[0].mismatches[0].diff.as_ref()).to(be_none());
+}
+
+fn mock_server_details() -> MockServerDetails {
+  MockServerDetails {
+    key: "1234".to_string(),
+    address: "127.0.0.1".to_string(),
+    port: 5678,
+    plugin: manifest()
+  }
+}
+
+#[tokio::test]
+async fn shutdown_mock_server_returns_the_results_if_all_the_interactions_matched() {
+  let plugin = MockPlugin {
+    shutdown_mock_server_response: ShutdownMockServerResponse {
+      ok: true,
+      results: vec![]
+    },
+    .. MockPlugin::default()
+  };
+
+  let results = shutdown_mock_server_with_plugin(&plugin, &mock_server_details()).await;
+
+  expect!(results.unwrap().is_empty()).to(be_true());
+}
+
+#[tokio::test]
+async fn shutdown_mock_server_returns_an_error_if_the_plugin_reports_a_failure() {
+  let plugin = MockPlugin {
+    shutdown_mock_server_response: ShutdownMockServerResponse {
+      ok: false,
+      results: vec![
+        MockServerResult {
+          interaction_key: "abc".to_string(),
+          path: "/Service/Method".to_string(),
+          error: String::default(),
+          mismatches: vec![
+            ContentMismatch {
+              mismatch: "Expected 100 but got 200".to_string(),
+              path: "$.id".to_string(),
+              .. ContentMismatch::default()
+            }
+          ]
+        },
+        MockServerResult {
+          path: "/Service/Other".to_string(),
+          error: "No interaction found".to_string(),
+          .. MockServerResult::default()
+        }
+      ]
+    },
+    .. MockPlugin::default()
+  };
+
+  let result = shutdown_mock_server_with_plugin(&plugin, &mock_server_details()).await;
+
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("Mock server 1234 did not match all the \
+    interactions - /Service/Method: Expected 100 but got 200; /Service/Other: No interaction found"));
+
+  let plugin = MockPlugin::default();
+  let result = shutdown_mock_server_with_plugin(&plugin, &mock_server_details()).await;
+  expect!(result.unwrap_err().to_string())
+    .to(be_equal_to("Plugin mock/1.2.3 failed to shut down mock server 1234"));
+}
@@ -47,7 +47,7 @@ async fn test_proto_client() {
   let proto_service = pact_builder
     .using_plugin("protobuf", None).await
     .synchronous_message_interaction("init plugin request", "core/interaction/synchronous-message", |mut i| async move {
-      let project_dir = Path::new(option_env!("CARGO_MANIFEST_DIR").unwrap());
+      let project_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
       println!("project_dir = {:?}", project_dir);
       let proto_file = project_dir.join("plugin.proto");
       println!("proto_file = {:?}", proto_file);
@@ -91,3 +91,51 @@ async fn test_proto_client() {
     expect!(result).to(be_ok());
   }
 }
+
+#[tokio::test]
+async fn init_handshake_rejects_plugins_with_an_unsupported_interface_version() {
+  let request = InitPluginRequest {
+    implementation: "plugin-driver-rust".to_string(),
+    .. InitPluginRequest::default()
+  };
+  let manifest = PactPluginManifest {
+    name: "Test".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+
+  let mock_plugin = MockPlugin {
+    request: request.clone(),
+    response: InitPluginResponse {
+      capabilities: vec!["CompareContentsBatch".to_string()],
+      interface_versions: vec![1, 2],
+      .. InitPluginResponse::default()
+    }
+  };
+  let response = init_handshake(&manifest, &mock_plugin).await.unwrap();
+  expect!(response.capabilities).to(be_equal_to(vec!["CompareContentsBatch".to_string()]));
+
+  let mock_plugin = MockPlugin {
+    request,
+    response: InitPluginResponse {
+      interface_versions: vec![2],
+      .. InitPluginResponse::default()
+    }
+  };
+  let result = init_handshake(&manifest, &mock_plugin).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to(
+    "Plugin Test/0.0.1 supports plugin interface versions [2], but this driver only supports [1]"));
+}
+
+#[tokio::test]
+async fn rpc_methods_added_after_the_first_interface_version_return_an_error_by_default() {
+  let mock_plugin = MockPlugin {
+    request: InitPluginRequest::default(),
+    response: InitPluginResponse::default()
+  };
+
+  let result = mock_plugin.compare_stream(CompareStreamRequest::default()).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("The CompareStream RPC is not supported by this plugin"));
+  let result = mock_plugin.verify_interaction(VerifyInteractionRequest::default()).await;
+  expect!(result.unwrap_err().to_string()).to(be_equal_to("The VerifyInteraction RPC is not supported by this plugin"));
+}
@@ -0,0 +1,133 @@
+use expectest::prelude::*;
+use maplit::hashmap;
+use serde_json::json;
+
+use pact_plugin_driver::content::PluginConfiguration;
+use pact_plugin_driver::pact_metadata::{
+  ConfigurationConflict,
+  MetadataMergeError,
+  merge_plugin_configuration,
+  plugin_interaction_config
+};
+use pact_plugin_driver::plugin_models::{PactPluginManifest, PluginInteractionConfig};
+
+fn manifest(name: &str, version: &str) -> PactPluginManifest {
+  PactPluginManifest {
+    name: name.to_string(),
+    version: version.to_string(),
+    .. PactPluginManifest::default()
+  }
+}
+
+fn configuration(pact_configuration: serde_json::Value) -> PluginConfiguration {
+  PluginConfiguration {
+    interaction_configuration: hashmap!{},
+    pact_configuration: pact_configuration.as_object().unwrap().iter()
+      .map(|(k, v)| (k.clone(), v.clone()))
+      .collect()
+  }
+}
+
+#[test]
+fn merge_plugin_configuration_adds_entries_sorted_by_plugin_name_and_version() {
+  let metadata = json!({ "pactSpecification": { "version": "4.0" } });
+
+  let metadata = merge_plugin_configuration(&metadata, &manifest("protobuf", "0.1.0"),
+    &configuration(json!({ "descriptors": { "abc": { "protoFile": "test.proto" } } }))).unwrap();
+  let metadata = merge_plugin_configuration(&metadata, &manifest("csv", "0.0.1"),
+    &configuration(json!({}))).unwrap();
+  let metadata = merge_plugin_configuration(&metadata, &manifest("protobuf", "0.1.0"),
+    &configuration(json!({ "descriptors": { "def": { "protoFile": "other.proto" } } }))).unwrap();
+
+  expect!(metadata).to(be_equal_to(json!({
+    "pactSpecification": { "version": "4.0" },
+    "plugins": [
+      { "name": "csv", "version": "0.0.1", "configuration": {} },
+      {
+        "name": "protobuf",
+        "version": "0.1.0",
+        "configuration": {
+          "descriptors": {
+            "abc": { "protoFile": "test.proto" },
+            "def": { "protoFile": "other.proto" }
+          }
+        }
+      }
+    ]
+  })));
+}
+
+#[test]
+fn merge_plugin_configuration_returns_all_the_conflicting_values() {
+  let metadata = json!({
+    "plugins": [
+      { "name": "protobuf", "version": "0.1.0", "configuration": { "a": 1, "b": { "c": "d" }, "e": [1] } }
+    ]
+  });
+
+  let result = merge_plugin_configuration(&metadata, &manifest("protobuf", "0.1.0"),
+    &configuration(json!({ "a": 1, "b": { "c": "x" }, "e": [2] })));
+
+  expect!(result).to(be_err().value(MetadataMergeError::Conflicts(vec![
+    ConfigurationConflict {
+      name: "protobuf".to_string(),
+      version: "0.1.0".to_string(),
+      path: "$.b.c".to_string(),
+      existing: json!("d"),
+      new: json!("x")
+    },
+    ConfigurationConflict {
+      name: "protobuf".to_string(),
+      version: "0.1.0".to_string(),
+      path: "$.e".to_string(),
+      existing: json!([1]),
+      new: json!([2])
+    }
+  ])));
+  expect!(merge_plugin_configuration(&metadata, &manifest("protobuf", "0.2.0"),
+    &configuration(json!({ "a": 2 })))).to(be_ok());
+  expect!(merge_plugin_configuration(&json!({ "plugins": {} }), &manifest("protobuf", "0.1.0"),
+    &configuration(json!({})))).to(be_err());
+}
+
+#[test]
+fn plugin_interaction_config_reads_the_pact_and_interaction_configuration() {
+  let metadata = json!({
+    "plugins": [
+      { "name": "csv", "version": "0.0.1", "configuration": { "a": 1 } },
+      { "name": "protobuf", "version": "0.1.0", "configuration": { "b": 2 } }
+    ]
+  });
+  let interaction_config = hashmap!{
+    "protobuf".to_string() => hashmap!{ "c".to_string() => json!(3) }
+  };
+
+  expect!(plugin_interaction_config(&metadata, &manifest("protobuf", "0.1.0"), &interaction_config))
+    .to(be_some().value(PluginInteractionConfig {
+      pact_configuration: hashmap!{ "b".to_string() => json!(2) },
+      interaction_configuration: hashmap!{ "c".to_string() => json!(3) }
+    }));
+  expect!(plugin_interaction_config(&metadata, &manifest("csv", "0.0.2"), &interaction_config))
+    .to(be_some().value(PluginInteractionConfig {
+      pact_configuration: hashmap!{ "a".to_string() => json!(1) },
+      interaction_configuration: hashmap!{}
+    }));
+  expect!(plugin_interaction_config(&metadata, &manifest("other", "0.0.1"), &interaction_config)).to(be_none());
+}
+
+#[test]
+fn plugin_versions_are_compared_as_semantic_versions() {
+  let metadata = merge_plugin_configuration(&json!({}), &manifest("csv", "0.0.10"),
+    &configuration(json!({ "a": 10 }))).unwrap();
+  let metadata = merge_plugin_configuration(&metadata, &manifest("csv", "0.0.9"),
+    &configuration(json!({ "a": 9 }))).unwrap();
+
+  expect!(metadata["plugins"].as_array().unwrap().iter()
+    .map(|entry| entry["version"].as_str().unwrap().to_string())
+    .collect::<Vec<_>>()).to(be_equal_to(vec!["0.0.9".to_string(), "0.0.10".to_string()]));
+  expect!(plugin_interaction_config(&metadata, &manifest("csv", "0.0.11"), &hashmap!{}))
+    .to(be_some().value(PluginInteractionConfig {
+      pact_configuration: hashmap!{ "a".to_string() => json!(10) },
+      interaction_configuration: hashmap!{}
+    }));
+}
@@ -0,0 +1,167 @@
+use std::fmt::Debug;
+
+use expectest::prelude::*;
+use maplit::hashmap;
+use prost::Message;
+use serde_json::{json, Value};
+
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::proto::catalogue_entry::EntryType;
+use pact_plugin_driver::proto_json::{message_from_json, message_to_json, method_types};
+use pact_plugin_driver::utils::{to_proto_struct, to_proto_value};
+
+// Converts the message to JSON, checks the JSON and then converts it back to the message
+fn expect_round_trip<M: Message + Default + PartialEq + Debug>(type_name: &str, message: M, expected_json: Value) {
+  let json = message_to_json(type_name, &message).unwrap();
+  expect!(json.clone()).to(be_equal_to(expected_json));
+  expect!(message_from_json::<M>(type_name, &json).unwrap()).to(be_equal_to(message));
+}
+
+#[test]
+fn string_and_bool_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.CompareContentsRequest", CompareContentsRequest {
+    allow_unexpected_keys: true,
+    .. CompareContentsRequest::default()
+  }, json!({ "allowUnexpectedKeys": true }));
+  expect_round_trip("io.pact.plugin.ContentTypeMismatch", ContentTypeMismatch {
+    expected: "text/csv".to_string(),
+    actual: "application/json".to_string()
+  }, json!({ "expected": "text/csv", "actual": "application/json" }));
+}
+
+#[test]
+fn uint32_and_int32_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.StreamCount", StreamCount { min: 1, max: 4_000_000_000 },
+    json!({ "min": 1, "max": 4_000_000_000u32 }));
+  expect_round_trip("io.pact.plugin.StreamMismatch", StreamMismatch {
+    expected_index: 2,
+    actual_index: -1,
+    .. StreamMismatch::default()
+  }, json!({ "expectedIndex": 2, "actualIndex": -1 }));
+}
+
+#[test]
+fn integer_fields_can_be_json_strings() {
+  let message = message_from_json::<StreamMismatch>("io.pact.plugin.StreamMismatch",
+    &json!({ "expectedIndex": "2", "actualIndex": "-1" })).unwrap();
+  expect!(message.expected_index).to(be_equal_to(2));
+  expect!(message.actual_index).to(be_equal_to(-1));
+}
+
+#[test]
+fn repeated_uint32_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.InitPluginResponse", InitPluginResponse {
+    interface_versions: vec![1, 2],
+    .. InitPluginResponse::default()
+  }, json!({ "interfaceVersions": [1, 2] }));
+}
+
+#[test]
+fn enum_fields_round_trip_as_the_value_name() {
+  expect_round_trip("io.pact.plugin.Body", Body {
+    content_type_hint: body::ContentTypeHint::Binary as i32,
+    .. Body::default()
+  }, json!({ "contentTypeHint": "BINARY" }));
+
+  let message = message_from_json::<Body>("io.pact.plugin.Body", &json!({ "contentTypeHint": 1 })).unwrap();
+  expect!(message.content_type_hint).to(be_equal_to(body::ContentTypeHint::Text as i32));
+}
+
+#[test]
+fn bytes_fields_round_trip_as_base64() {
+  expect_round_trip("io.pact.plugin.MetadataValue", MetadataValue {
+    value: Some(metadata_value::Value::BinaryValue(vec![0, 1, 2, 255]))
+  }, json!({ "binaryValue": "AAEC/w==" }));
+}
+
+#[test]
+fn bytes_value_fields_round_trip_as_the_wrapped_value() {
+  expect_round_trip("io.pact.plugin.Body", Body {
+    content_type: "application/octet-stream".to_string(),
+    content: Some(vec![0, 1, 2, 255]),
+    .. Body::default()
+  }, json!({ "contentType": "application/octet-stream", "content": "AAEC/w==" }));
+  expect_round_trip("io.pact.plugin.Body", Body {
+    content: Some(vec![]),
+    .. Body::default()
+  }, json!({ "content": "" }));
+}
+
+#[test]
+fn message_and_repeated_message_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.InitPluginResponse", InitPluginResponse {
+    catalogue: vec![
+      CatalogueEntry { r#type: EntryType::ContentMatcher as i32, key: "csv".to_string(), values: hashmap!{} },
+      CatalogueEntry { r#type: EntryType::MockServer as i32, key: "grpc".to_string(), values: hashmap!{} }
+    ],
+    .. InitPluginResponse::default()
+  }, json!({
+    "catalogue": [
+      { "key": "csv" },
+      { "type": "MOCK_SERVER", "key": "grpc" }
+    ]
+  }));
+}
+
+#[test]
+fn map_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.CatalogueEntry", CatalogueEntry {
+    key: "csv".to_string(),
+    values: hashmap!{ "content-types".to_string() => "text/csv".to_string() },
+    .. CatalogueEntry::default()
+  }, json!({ "key": "csv", "values": { "content-types": "text/csv" } }));
+  expect_round_trip("io.pact.plugin.CompareContentsResponse", CompareContentsResponse {
+    results: hashmap!{
+      "$".to_string() => ContentMismatches {
+        mismatches: vec![ContentMismatch { mismatch: "Bad".to_string(), .. ContentMismatch::default() }]
+      }
+    },
+    .. CompareContentsResponse::default()
+  }, json!({ "results": { "$": { "mismatches": [ { "mismatch": "Bad" } ] } } }));
+}
+
+#[test]
+fn oneof_fields_round_trip() {
+  expect_round_trip("io.pact.plugin.VerificationPreparationResponse", VerificationPreparationResponse {
+    response: Some(verification_preparation_response::Response::Error("failed".to_string()))
+  }, json!({ "error": "failed" }));
+}
+
+#[test]
+fn struct_and_value_fields_round_trip_as_json() {
+  expect_round_trip("io.pact.plugin.CompareContentsRequest", CompareContentsRequest {
+    expected_metadata: Some(to_proto_struct(hashmap!{ "a".to_string() => json!({ "b": [1, "two", null, true] }) })),
+    .. CompareContentsRequest::default()
+  }, json!({ "expectedMetadata": { "a": { "b": [1.0, "two", null, true] } } }));
+  expect_round_trip("io.pact.plugin.MetadataValue", MetadataValue {
+    value: Some(metadata_value::Value::NonBinaryValue(to_proto_value(&json!(null))))
+  }, json!({ "nonBinaryValue": null }));
+}
+
+#[test]
+fn empty_messages_round_trip() {
+  let (_, output) = method_types("io.pact.plugin.PactPlugin.UpdateCatalogue").unwrap();
+  expect!(output).to(be_equal_to("google.protobuf.Empty"));
+  expect_round_trip(output, (), json!({}));
+}
+
+#[test]
+fn message_from_json_rejects_unknown_fields() {
+  let result = message_from_json::<MatchValueRequest>("io.pact.plugin.MatchValueRequest", &json!({ "path": "$", "other": 1 }));
+  expect!(format!("{:#}", result.unwrap_err())).to(be_equal_to(
+    "Could not convert JSON to io.pact.plugin.MatchValueRequest: io.pact.plugin.MatchValueRequest does not have a field 'other'"));
+}
+
+#[test]
+fn message_from_json_rejects_integers_that_are_out_of_range() {
+  let result = message_from_json::<StreamCount>("io.pact.plugin.StreamCount", &json!({ "min": -1 }));
+  expect!(format!("{:#}", result.unwrap_err())).to(be_equal_to(
+    "Could not convert JSON to io.pact.plugin.StreamCount: Field 'min' has an invalid value - -1 is out of range"));
+}
+
+#[test]
+fn field_types_not_used_by_the_plugin_proto_file_are_not_supported() {
+  let result = message_from_json::<f64>("google.protobuf.DoubleValue", &json!({ "value": 1.5 }));
+  expect!(format!("{:#}", result.unwrap_err())).to(be_equal_to(
+    "Could not convert JSON to google.protobuf.DoubleValue: Field 'value' has type Double, which is not used by the plugin proto file"));
+}
@@ -0,0 +1,160 @@
+use std::fs;
+
+use bytes::Bytes;
+use expectest::prelude::*;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::matchingrules::{Category, MatchingRuleCategory};
+use pact_models::pact::Pact;
+use pact_models::PactSpecification;
+use pact_models::prelude::ContentType;
+use pact_models::v4::pact::V4Pact;
+use pact_models::v4::sync_message::SynchronousMessage;
+use serde_json::json;
+
+use pact_plugin_driver::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType, CatalogueEntryType};
+use pact_plugin_driver::content::{ContentComparison, ContentMatchError, ContentMatcher, ContentMetadata};
+use pact_plugin_driver::plugin_models::{PactPluginManifest, PactPluginRpc};
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::recording::{RecordedCall, ReplayPlugin, request_to_json, response_to_json};
+use pact_plugin_driver::utils::to_proto_struct;
+use pact_plugin_driver::verification::{InteractionVerificationData, verify_interaction_with_plugin};
+
+fn recorded_call(method: &str, request: serde_json::Value, response: serde_json::Value) -> RecordedCall {
+  RecordedCall {
+    plugin: "mock".to_string(),
+    version: "1.2.3".to_string(),
+    method: method.to_string(),
+    request,
+    response: Some(response),
+    error: None
+  }
+}
+
+#[tokio::test]
+async fn replay_plugin_returns_the_recorded_responses() {
+  let request = |path: &str| MatchValueRequest { path: path.to_string(), .. MatchValueRequest::default() };
+  let calls = vec![
+    recorded_call("MatchValue", json!({ "path": "$.a" }), json!({ "mismatches": ["a"] })),
+    recorded_call("MatchValue", json!({ "path": "$.b" }), json!({ "mismatches": ["b"] })),
+    RecordedCall {
+      method: "GenerateValue".to_string(),
+      request: json!({}),
+      error: Some("plugin crashed".to_string()),
+      .. RecordedCall::default()
+    }
+  ];
+  let plugin = ReplayPlugin::new(calls);
+
+  let response = plugin.match_value(request("$.b")).await.unwrap();
+  expect!(response.mismatches).to(be_equal_to(vec!["b".to_string()]));
+  expect!(plugin.match_value(request("$.b")).await.unwrap_err().to_string())
+    .to(be_equal_to("None of the recorded MatchValue calls match the request"));
+  expect!(plugin.match_value(request("$.c")).await.unwrap_err().to_string())
+    .to(be_equal_to("None of the recorded MatchValue calls match the request"));
+  let response = plugin.match_value(request("$.a")).await.unwrap();
+  expect!(response.mismatches).to(be_equal_to(vec!["a".to_string()]));
+  expect!(plugin.match_value(request("$.a")).await.unwrap_err().to_string())
+    .to(be_equal_to("There is no recorded MatchValue call to replay"));
+  expect!(plugin.generate_value(GenerateValueRequest::default()).await.unwrap_err().to_string())
+    .to(be_equal_to("plugin crashed"));
+}
+
+#[tokio::test]
+async fn replay_plugin_can_rerun_a_content_comparison() {
+  let plugin = ReplayPlugin::new(vec![
+    recorded_call("CompareContents", json!({
+      "expected": { "contentType": "text/csv", "content": "YSxi" },
+      "actual": { "contentType": "text/csv", "content": "YSxj" }
+    }), json!({
+      "results": { "column:2": { "mismatches": [ { "mismatch": "Expected 'b' but got 'c'", "path": "column:2" } ] } }
+    }))
+  ]);
+  let matcher = ContentMatcher {
+    catalogue_entry: CatalogueEntry {
+      entry_type: CatalogueEntryType::CONTENT_MATCHER,
+      provider_type: CatalogueEntryProviderType::PLUGIN,
+      plugin: Some(PactPluginManifest {
+        name: "mock".to_string(),
+        version: "1.2.3".to_string(),
+        .. PactPluginManifest::default()
+      }),
+      key: "plugin/mock/content-matcher/csv".to_string(),
+      values: hashmap!{ "content-types".to_string() => "text/csv".to_string() }
+    }
+  };
+  let comparison = |actual: &str| ContentComparison {
+    id: String::default(),
+    expected: OptionalBody::Present(Bytes::from("a,b"), Some(ContentType::from("text/csv")), None),
+    actual: OptionalBody::Present(Bytes::from(actual.to_string()), Some(ContentType::from("text/csv")), None),
+    context: MatchingRuleCategory::empty(Category::BODY),
+    metadata: ContentMetadata::default(),
+    allow_unexpected_keys: false,
+    plugin_config: None
+  };
+
+  let result = matcher.match_contents_with_plugin(&plugin, &comparison("a,d")).await;
+  expect!(matches!(result, Err(ContentMatchError::TransportError(err)) if err == "None of the recorded CompareContents calls match the request"))
+    .to(be_true());
+  let result = matcher.match_contents_with_plugin(&plugin, &comparison("a,c")).await;
+  match result {
+    Err(ContentMatchError::Mismatches(mismatches)) => {
+      let mismatches = &mismatches[&Category::BODY]["column:2"];
+      expect!(mismatches.len()).to(be_equal_to(1));
+      expect!(mismatches[0].mismatch.as_str()).to(be_equal_to("Expected 'b' but got 'c'"));
+    }
+    _ => panic!("Expected mismatches, got {:?}", result)
+  }
+}
+
+#[tokio::test]
+async fn replay_plugin_can_rerun_a_verification_from_a_recording_file() {
+  let data = InteractionVerificationData {
+    request_data: OptionalBody::from("{}"),
+    metadata: hashmap!{}
+  };
+  let pact = V4Pact::default();
+  let interaction = SynchronousMessage { key: Some("1234".to_string()), .. SynchronousMessage::default() };
+  let verification_request = request_to_json("VerifyInteraction", &VerifyInteractionRequest {
+    interaction_data: Some((&data).into()),
+    config: Some(to_proto_struct(hashmap!{})),
+    pact: pact.to_json(PactSpecification::V4).unwrap().to_string(),
+    interaction_key: "1234".to_string()
+  }).unwrap();
+  let response = VerifyInteractionResponse {
+    response: Some(verify_interaction_response::Response::Result(VerificationResult {
+      success: false,
+      output: vec!["Verifying".to_string()],
+      .. VerificationResult::default()
+    }))
+  };
+  let calls = [
+    recorded_call("InitPlugin", json!({}), json!({ "capabilities": ["VerifyInteraction"] })),
+    recorded_call("VerifyInteraction", verification_request, response_to_json("VerifyInteraction", &response).unwrap()),
+    RecordedCall { plugin: "other".to_string(), .. recorded_call("VerifyInteraction", json!({}), json!({})) }
+  ];
+  let path = std::env::temp_dir().join(format!("plugin-recording-{}.jsonl", uuid::Uuid::new_v4()));
+  let contents: Vec<String> = calls.iter().map(|call| serde_json::to_string(call).unwrap()).collect();
+  fs::write(&path, contents.join("\n")).unwrap();
+
+  let plugin = ReplayPlugin::load(&path, Some("mock")).unwrap();
+  fs::remove_file(&path).unwrap();
+  let transport_entry = CatalogueEntry {
+    entry_type: CatalogueEntryType::MOCK_SERVER,
+    provider_type: CatalogueEntryProviderType::PLUGIN,
+    plugin: Some(PactPluginManifest {
+      name: "mock".to_string(),
+      version: "1.2.3".to_string(),
+      .. PactPluginManifest::default()
+    }),
+    key: "plugin/mock/mock-server/grpc".to_string(),
+    values: hashmap!{}
+  };
+
+  expect!(plugin.capabilities()).to(be_some().value(vec!["VerifyInteraction".to_string()]));
+  let result = verify_interaction_with_plugin(&plugin, &transport_entry, &data, &hashmap!{}, &pact,
+    &interaction).await.unwrap();
+  expect!(result.ok).to(be_false());
+  expect!(result.output).to(be_equal_to(vec!["Verifying".to_string()]));
+  expect!(plugin.verify_interaction(VerifyInteractionRequest::default()).await).to(be_err());
+}
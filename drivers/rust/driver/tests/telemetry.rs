@@ -0,0 +1,42 @@
+use std::fs;
+
+use expectest::prelude::*;
+use serde_json::json;
+
+use pact_plugin_driver::plugin_manager::init_handshake;
+use pact_plugin_driver::plugin_models::{PactPluginManifest, SUPPORTED_INTERFACE_VERSIONS};
+use pact_plugin_driver::proto::InitPluginRequest;
+use pact_plugin_driver::recording::{RecordedCall, ReplayPlugin, request_to_json};
+use pact_plugin_driver::telemetry::{init_telemetry, shutdown_telemetry, TelemetryExporter};
+
+#[tokio::test]
+async fn driver_operations_are_exported_as_spans() {
+  let path = std::env::temp_dir().join(format!("plugin-traces-{}.txt", uuid::Uuid::new_v4()));
+  init_telemetry(TelemetryExporter::File(path.clone()), "telemetry-test").unwrap();
+
+  let plugin = ReplayPlugin::new(vec![
+    RecordedCall {
+      method: "InitPlugin".to_string(),
+      request: request_to_json("InitPlugin", &InitPluginRequest {
+        implementation: "plugin-driver-rust".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        interface_versions: SUPPORTED_INTERFACE_VERSIONS.to_vec(),
+        .. InitPluginRequest::default()
+      }).unwrap(),
+      response: Some(json!({})),
+      .. RecordedCall::default()
+    }
+  ]);
+  let manifest = PactPluginManifest {
+    name: "traced".to_string(),
+    version: "0.0.1".to_string(),
+    .. PactPluginManifest::default()
+  };
+  expect!(init_handshake(&manifest, &plugin).await).to(be_ok());
+  shutdown_telemetry();
+
+  let traces = fs::read_to_string(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+  expect!(traces.contains("name: \"init_handshake\"")).to(be_true());
+  expect!(traces.contains("telemetry-test")).to(be_true());
+}
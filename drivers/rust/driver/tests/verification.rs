@@ -0,0 +1,163 @@
+use bytes::Bytes;
+use expectest::prelude::*;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::v4::sync_message::SynchronousMessage;
+use serde_json::json;
+
+use pact_plugin_driver::catalogue_manager::{CatalogueEntry, CatalogueEntryProviderType, CatalogueEntryType};
+use pact_plugin_driver::proto::*;
+use pact_plugin_driver::utils::to_proto_value;
+use pact_plugin_driver::verification::{
+  InteractionVerificationData,
+  InteractionVerificationDetails,
+  MetadataValue,
+  prepare_validation_for_interaction,
+  prepare_validation_for_interaction_with_plugin,
+  verify_interaction,
+  verify_interaction_with_plugin
+};
+
+use crate::common::{MockPlugin, json_body, mock_server_entry, pact};
+
+mod common;
+
+fn capabilities() -> Option<Vec<String>> {
+  Some(vec!["VerifyInteraction".to_string()])
+}
+
+#[tokio::test]
+async fn prepare_validation_for_interaction_sends_the_interaction_key_and_returns_the_request_data() {
+  let plugin = MockPlugin {
+    prepare_response: VerificationPreparationResponse {
+      response: Some(verification_preparation_response::Response::InteractionData(InteractionData {
+        body: json_body("{}"),
+        metadata: hashmap!{
+          "key".to_string() => pact_plugin_driver::proto::MetadataValue {
+            value: Some(metadata_value::Value::NonBinaryValue(to_proto_value(&json!("value"))))
+          },
+          "bin".to_string() => pact_plugin_driver::proto::MetadataValue {
+            value: Some(metadata_value::Value::BinaryValue(vec![1, 2, 3]))
+          }
+        }
+      }))
+    },
+    capabilities: capabilities(),
+    .. MockPlugin::default()
+  };
+  let interaction = SynchronousMessage {
+    key: Some("abc123".to_string()),
+    description: "test interaction".to_string(),
+    .. SynchronousMessage::default()
+  };
+  let config = hashmap!{ "host".to_string() => json!("localhost") };
+
+  let data = prepare_validation_for_interaction_with_plugin(&plugin, &mock_server_entry(), &pact(),
+    &interaction, &config).await.unwrap();
+
+  expect!(data.request_data.value()).to(be_some().value(Bytes::from("{}")));
+  expect!(data.metadata).to(be_equal_to(hashmap!{
+    "key".to_string() => MetadataValue::Json(json!("value")),
+    "bin".to_string() => MetadataValue::Binary(Bytes::from(vec![1, 2, 3]))
+  }));
+  let request = plugin.prepare_request.lock().unwrap().clone().unwrap();
+  expect!(request.interaction_key).to(be_equal_to("abc123"));
+  expect!(request.config.unwrap().fields.contains_key("host")).to(be_true());
+}
+
+#[tokio::test]
+async fn prepare_validation_for_interaction_returns_an_error_from_the_plugin() {
+  let plugin = MockPlugin {
+    prepare_response: VerificationPreparationResponse {
+      response: Some(verification_preparation_response::Response::Error("no such interaction".to_string()))
+    },
+    capabilities: capabilities(),
+    .. MockPlugin::default()
+  };
+
+  let result = prepare_validation_for_interaction_with_plugin(&plugin, &mock_server_entry(), &pact(),
+    &SynchronousMessage::default(), &hashmap!{}).await;
+
+  expect!(result.unwrap_err().to_string()).to(be_equal_to(
+    "Plugin mock/1.2.3 failed to prepare the interaction for verification - no such interaction"));
+}
+
+#[tokio::test]
+async fn verification_returns_an_error_if_the_transport_is_not_provided_by_a_plugin() {
+  let transport_entry = CatalogueEntry {
+    entry_type: CatalogueEntryType::MOCK_SERVER,
+    provider_type: CatalogueEntryProviderType::CORE,
+    plugin: None,
+    key: "http".to_string(),
+    values: hashmap!{}
+  };
+  let data = InteractionVerificationData {
+    request_data: OptionalBody::Missing,
+    metadata: hashmap!{}
+  };
+
+  let result = prepare_validation_for_interaction(&transport_entry, &pact(), &SynchronousMessage::default(),
+    &hashmap!{}, None).await;
+  expect!(result.unwrap_err().to_string())
+    .to(be_equal_to("Transport 'core/mock-server/http' is provided by the core framework, not a plugin"));
+  let result = verify_interaction(&transport_entry, &data, &hashmap!{}, &pact(),
+    &SynchronousMessage::default(), None).await;
+  expect!(result.unwrap_err().to_string())
+    .to(be_equal_to("Transport 'core/mock-server/http' is provided by the core framework, not a plugin"));
+}
+
+#[tokio::test]
+async fn verify_interaction_sends_the_request_data_and_converts_the_result() {
+  let plugin = MockPlugin {
+    verify_response: VerifyInteractionResponse {
+      response: Some(verify_interaction_response::Response::Result(VerificationResult {
+        success: false,
+        response_data: None,
+        mismatches: vec![
+          VerificationResultItem {
+            result: Some(verification_result_item::Result::Error("connection reset".to_string()))
+          },
+          VerificationResultItem {
+            result: Some(verification_result_item::Result::Mismatch(ContentMismatch {
+              expected: Some("100".as_bytes().to_vec()),
+              actual: Some("200".as_bytes().to_vec()),
+              mismatch: "Expected 100 but got 200".to_string(),
+              path: "$.id".to_string(),
+              diff: String::default()
+            }))
+          }
+        ],
+        output: vec!["Verifying a gRPC method".to_string()]
+      }))
+    },
+    capabilities: capabilities(),
+    .. MockPlugin::default()
+  };
+  let interaction = SynchronousMessage {
+    description: "test interaction".to_string(),
+    .. SynchronousMessage::default()
+  };
+  let data = InteractionVerificationData {
+    request_data: OptionalBody::from("{}"),
+    metadata: hashmap!{ "key".to_string() => MetadataValue::Json(json!(100)) }
+  };
+
+  let result = verify_interaction_with_plugin(&plugin, &mock_server_entry(), &data, &hashmap!{}, &pact(),
+    &interaction).await.unwrap();
+
+  expect!(result.ok).to(be_false());
+  expect!(result.output).to(be_equal_to(vec!["Verifying a gRPC method".to_string()]));
+  expect!(result.details.len()).to(be_equal_to(2));
+  expect!(matches!(&result.details[0], InteractionVerificationDetails::Error(err) if err == "connection reset"))
+    .to(be_true());
+  expect!(matches!(&result.details[1], InteractionVerificationDetails::Mismatch(mismatch) if mismatch.path == "$.id"))
+    .to(be_true());
+
+  let request = plugin.verify_request.lock().unwrap().clone().unwrap();
+  let interaction_data = request.interaction_data.unwrap();
+  expect!(interaction_data.body.unwrap().content).to(be_some().value("{}".as_bytes().to_vec()));
+  expect!(interaction_data.metadata.contains_key("key")).to(be_true());
+  expect!(request.interaction_key.is_empty()).to(be_false());
+  let pact_json: serde_json::Value = serde_json::from_str(request.pact.as_str()).unwrap();
+  expect!(pact_json["provider"]["name"].as_str()).to(be_some().value("provider"));
+}
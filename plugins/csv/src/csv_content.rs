@@ -7,7 +7,7 @@ use itertools::Itertools;
 use log::debug;
 use maplit::hashmap;
 use pact_models::bodies::OptionalBody;
-use pact_models::generators::{GenerateValue, Generator, NoopVariantMatcher, VariantMatcher};
+use pact_models::generators::{GenerateValue, Generator, GeneratorTestMode, NoopVariantMatcher, VariantMatcher};
 use pact_models::prelude::ContentType;
 use serde_json::{json, Value};
 use tonic::{Request, Response};
@@ -158,6 +158,11 @@ pub fn generate_csv_content(
 ) -> anyhow::Result<OptionalBody> {
   let request = request.get_ref();
   let has_headers = has_headers(&request.plugin_configuration);
+  let test_mode = match proto::generate_content_request::TestMode::from_i32(request.test_mode) {
+    Some(proto::generate_content_request::TestMode::Consumer) => Some(GeneratorTestMode::Consumer),
+    Some(proto::generate_content_request::TestMode::Provider) => Some(GeneratorTestMode::Provider),
+    _ => None
+  };
 
   let mut generators = hashmap! {};
   for (key, gen) in &request.generators {
@@ -167,10 +172,17 @@ pub fn generate_csv_content(
     }).collect();
     let generator = Generator::from_map(&gen.r#type, &values)
       .ok_or(anyhow!("Failed to build generator of type {}", gen.r#type))?;
-    generators.insert(column, generator);
+    if test_mode.as_ref().map(|mode| generator.corresponds_to_mode(mode)).unwrap_or(true) {
+      generators.insert(column, generator);
+    }
   };
 
-  let context = hashmap! {};
+  let context_values = request.test_context.as_ref()
+    .map(proto_struct_to_map)
+    .unwrap_or_default();
+  let context = context_values.iter()
+    .map(|(k, v)| (k.as_str(), v.clone()))
+    .collect();
   let variant_matcher = NoopVariantMatcher.boxed();
   let mut wtr = Writer::from_writer(vec![]);
 
@@ -190,14 +202,14 @@ pub fn generate_csv_content(
         if let Some(generator) = generators.get(&Right(headers.get(col).unwrap_or_default().to_string())) {
           let value = generator.generate_value(&field.to_string(), &context, &variant_matcher)?;
           wtr.write_field(value)?;
-        } else if let Some(generator) = generators.get(&Left(col)) {
+        } else if let Some(generator) = generators.get(&Left(col + 1)) {
           let value = generator.generate_value(&field.to_string(), &context, &variant_matcher)?;
           wtr.write_field(value)?;
         } else {
           wtr.write_field(field)?;
         }
       } else {
-        if let Some(generator) = generators.get(&Left(col)) {
+        if let Some(generator) = generators.get(&Left(col + 1)) {
           let value = generator.generate_value(&field.to_string(), &context, &variant_matcher)?;
           wtr.write_field(value)?;
         } else {
@@ -210,7 +222,10 @@ pub fn generate_csv_content(
   let generated = wtr.into_inner()?;
   debug!("Generated contents has {} bytes", generated.len());
   let bytes = Bytes::from(generated);
-  Ok(OptionalBody::Present(bytes, Some(ContentType::from("text/csv;charset=UTF-8")), None))
+  let content_type = request.contents.as_ref()
+    .and_then(|contents| ContentType::parse(contents.content_type.as_str()).ok())
+    .unwrap_or_else(|| ContentType::from("text/csv;charset=UTF-8"));
+  Ok(OptionalBody::Present(bytes, Some(content_type), None))
 }
 
 pub fn has_headers(plugin_config: &Option<proto::PluginConfiguration>) -> bool {
@@ -225,3 +240,74 @@ pub fn has_headers(plugin_config: &Option<proto::PluginConfiguration>) -> bool {
     None => true
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_plugin_driver::utils::to_proto_struct;
+  use serde_json::json;
+  use tonic::Request;
+
+  use crate::proto;
+
+  use super::generate_csv_content;
+
+  fn generate_request(has_headers: Option<bool>, contents: &str) -> Request<proto::GenerateContentRequest> {
+    Request::new(proto::GenerateContentRequest {
+      contents: Some(proto::Body {
+        content_type: "text/csv".to_string(),
+        content: Some(contents.as_bytes().to_vec()),
+        content_type_hint: 0
+      }),
+      generators: hashmap!{
+        "column:2".to_string() => proto::Generator {
+          r#type: "RandomInt".to_string(),
+          values: Some(to_proto_struct(hashmap!{ "min".to_string() => json!(7), "max".to_string() => json!(7) }))
+        }
+      },
+      plugin_configuration: has_headers.map(|has_headers| proto::PluginConfiguration {
+        interaction_configuration: Some(to_proto_struct(hashmap!{ "csvHeaders".to_string() => json!(has_headers) })),
+        pact_configuration: None
+      }),
+      .. proto::GenerateContentRequest::default()
+    })
+  }
+
+  #[test]
+  fn generate_csv_content_without_headers_keeps_the_first_row() {
+    let result = generate_csv_content(&generate_request(Some(false), "a,1\nb,2\n")).unwrap();
+    expect!(result.value().map(|v| String::from_utf8_lossy(&v).to_string()))
+      .to(be_some().value("a,7\nb,7\n"));
+  }
+
+  #[test]
+  fn generate_csv_content_with_headers() {
+    let result = generate_csv_content(&generate_request(None, "name,count\na,1\n")).unwrap();
+    expect!(result.value().map(|v| String::from_utf8_lossy(&v).to_string()))
+      .to(be_some().value("name,count\na,7\n"));
+  }
+
+  #[test]
+  fn generate_csv_content_uses_the_test_context_in_provider_mode() {
+    let request = Request::new(proto::GenerateContentRequest {
+      contents: Some(proto::Body {
+        content_type: "text/csv".to_string(),
+        content: Some("name,id\na,1\n".as_bytes().to_vec()),
+        content_type_hint: 0
+      }),
+      generators: hashmap!{
+        "column:2".to_string() => proto::Generator {
+          r#type: "ProviderState".to_string(),
+          values: Some(to_proto_struct(hashmap!{ "expression".to_string() => json!("${id}") }))
+        }
+      },
+      plugin_configuration: None,
+      test_mode: proto::generate_content_request::TestMode::Provider as i32,
+      test_context: Some(to_proto_struct(hashmap!{ "id".to_string() => json!("1000") }))
+    });
+    let result = generate_csv_content(&request).unwrap();
+    expect!(result.value().map(|v| String::from_utf8_lossy(&v).to_string()))
+      .to(be_some().value("name,id\na,1000\n"));
+  }
+}
@@ -9,17 +9,19 @@ use anyhow::anyhow;
 use csv::{Reader, ReaderBuilder, StringRecord};
 use env_logger::Env;
 use futures::Stream;
+use itertools::Itertools;
 use log::debug;
 use maplit::hashmap;
 use pact_matching::matchers::Matches;
-use pact_models::matchingrules::{MatchingRule, RuleList, RuleLogic};
+use pact_models::matchingrules::MatchingRule;
 use pact_models::prelude::ContentType;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use tokio::net::{TcpListener, TcpStream};
 use tonic::{Response, transport::Server};
 use uuid::Uuid;
 
 use crate::csv_content::{generate_csv_content, has_headers, setup_csv_contents};
+use crate::matchers::{apply_matching_rule, CSV_LENGTH_MATCHER, match_value};
 use crate::proto::body::ContentTypeHint;
 use crate::proto::catalogue_entry::EntryType;
 use crate::proto::pact_plugin_server::{PactPlugin, PactPluginServer};
@@ -29,6 +31,8 @@ mod proto;
 mod parser;
 mod utils;
 mod csv_content;
+mod matchers;
+mod telemetry;
 
 #[derive(Debug, Default)]
 pub struct CsvPactPlugin {}
@@ -49,7 +53,8 @@ impl PactPlugin for CsvPactPlugin {
           r#type: EntryType::ContentMatcher as i32,
           key: "csv".to_string(),
           values: hashmap! {
-            "content-types".to_string() => "text/csv;application/csv".to_string()
+            "content-types".to_string() => "text/csv;application/csv".to_string(),
+            "batch-compare".to_string() => "true".to_string()
           }
         },
         proto::CatalogueEntry {
@@ -58,8 +63,18 @@ impl PactPlugin for CsvPactPlugin {
           values: hashmap! {
             "content-types".to_string() => "text/csv;application/csv".to_string()
           }
+        },
+        proto::CatalogueEntry {
+          r#type: EntryType::Matcher as i32,
+          key: CSV_LENGTH_MATCHER.to_string(),
+          values: hashmap! {}
         }
-      ]
+      ],
+      capabilities: vec![
+        "CompareContentsBatch".to_string(),
+        "CompareStream".to_string()
+      ],
+      interface_versions: vec![1]
     }))
   }
 
@@ -93,22 +108,18 @@ impl PactPlugin for CsvPactPlugin {
 
         let rules = request.rules.iter()
           .map(|(key, rules)| {
-            let rules = rules.rule.iter().fold(RuleList::empty(RuleLogic::And), |mut list, rule| {
-              match to_object(&rule.values.as_ref().unwrap()) {
-                Value::Object(mut map) => {
-                  map.insert("match".to_string(), Value::String(rule.r#type.clone()));
-                  debug!("Creating matching rule with {:?}", map);
-                  list.add_rule(&MatchingRule::from_json(&Value::Object(map)).unwrap());
-                }
-                _ => {}
-              }
-              list
-            });
-            (key.clone(), rules)
-          }).collect();
-        compare_contents(has_headers, &mut expected_rdr, &mut actual_rdr,
-                         request.allow_unexpected_keys, rules)
-          .map_err(|err| tonic::Status::aborted(format!("Failed to compare CSV contents: {}", err)))
+            rules.rule.iter().map(column_rule).collect::<anyhow::Result<Vec<_>>>()
+              .map(|rules| (key.clone(), rules))
+          }).collect::<anyhow::Result<HashMap<_, _>>>();
+        match rules {
+          Ok(rules) => compare_contents(has_headers, &mut expected_rdr, &mut actual_rdr,
+                                        request.allow_unexpected_keys, rules)
+            .map_err(|err| tonic::Status::aborted(format!("Failed to compare CSV contents: {}", err))),
+          Err(err) => Ok(Response::new(proto::CompareContentsResponse {
+            error: err.to_string(),
+            .. proto::CompareContentsResponse::default()
+          }))
+        }
       }
       (None, Some(actual)) => {
         let contents = actual.content.as_ref().unwrap();
@@ -127,7 +138,8 @@ impl PactPlugin for CsvPactPlugin {
                 }
               ]
             }
-          }
+          },
+          .. proto::CompareContentsResponse::default()
         }))
       }
       (Some(expected), None) => {
@@ -147,19 +159,123 @@ impl PactPlugin for CsvPactPlugin {
                 }
               ]
             }
-          }
+          },
+          .. proto::CompareContentsResponse::default()
         }))
       }
       (None, None) => {
         Ok(Response::new(proto::CompareContentsResponse {
           error: String::default(),
           type_mismatch: None,
-          results: hashmap!{}
+          results: hashmap!{},
+          .. proto::CompareContentsResponse::default()
         }))
       }
     }
   }
 
+  // Request to compare a batch of CSV contents. Each comparison is done in turn, with any failures
+  // returned as the error for that comparison
+  async fn compare_contents_batch(
+    &self,
+    request: tonic::Request<proto::CompareContentsBatchRequest>,
+  ) -> Result<tonic::Response<proto::CompareContentsBatchResponse>, tonic::Status> {
+    let mut results = vec![];
+    for comparison in &request.get_ref().comparisons {
+      let response = match self.compare_contents(tonic::Request::new(comparison.request.clone().unwrap_or_default())).await {
+        Ok(response) => response.into_inner(),
+        Err(status) => proto::CompareContentsResponse {
+          error: status.message().to_string(),
+          .. proto::CompareContentsResponse::default()
+        }
+      };
+      results.push(proto::compare_contents_batch_response::ComparisonResult {
+        id: comparison.id.clone(),
+        response: Some(response)
+      });
+    }
+    Ok(Response::new(proto::CompareContentsBatchResponse { results }))
+  }
+
+  // Compare a stream of CSV messages. Each expected message consumes the actual messages that match
+  // it (up to the maximum count), and it is a mismatch if less than the minimum count matched. Any
+  // actual messages left over at the end are unexpected, and an error comparing a message is a mismatch
+  // for that message.
+  async fn compare_stream(
+    &self,
+    request: tonic::Request<proto::CompareStreamRequest>,
+  ) -> Result<tonic::Response<proto::CompareStreamResponse>, tonic::Status> {
+    let request = request.get_ref();
+    debug!("compare_stream request - {:?}", request);
+
+    let mut mismatches = vec![];
+    let mut actual_index = 0;
+    for (expected_index, expected) in request.expected.iter().enumerate() {
+      let (min, max) = match &expected.count {
+        Some(count) => (count.min as usize, if count.max == 0 { usize::MAX } else { count.max as usize }),
+        None => (1, 1)
+      };
+
+      let mut received = 0;
+      let mut content_mismatches = None;
+      while received < max && actual_index < request.actual.len() {
+        let actual = &request.actual[actual_index];
+        if actual.direction != expected.direction {
+          break;
+        }
+        let response = match self.compare_contents(tonic::Request::new(proto::CompareContentsRequest {
+          expected: expected.contents.clone(),
+          actual: actual.contents.clone(),
+          allow_unexpected_keys: request.allow_unexpected_keys,
+          rules: expected.rules.clone(),
+          plugin_configuration: request.plugin_configuration.clone(),
+          .. proto::CompareContentsRequest::default()
+        })).await {
+          Ok(response) => response.into_inner(),
+          Err(status) => proto::CompareContentsResponse {
+            error: status.message().to_string(),
+            .. proto::CompareContentsResponse::default()
+          }
+        };
+        let results = stream_content_mismatches(&response);
+        if results.is_empty() {
+          received += 1;
+          actual_index += 1;
+        } else {
+          content_mismatches = Some(results);
+          break;
+        }
+      }
+
+      if received < min {
+        mismatches.push(proto::StreamMismatch {
+          expected_index: expected_index as u32,
+          actual_index: if content_mismatches.is_some() { actual_index as i32 } else { -1 },
+          mismatch: format!("Expected message {} to be received at least {} time(s) but it was received {} time(s)",
+            expected_index + 1, min, received),
+          content_mismatches: content_mismatches.clone().unwrap_or_default()
+        });
+        if content_mismatches.is_some() {
+          actual_index += 1;
+        }
+      }
+    }
+
+    for index in actual_index..request.actual.len() {
+      mismatches.push(proto::StreamMismatch {
+        expected_index: request.expected.len() as u32,
+        actual_index: index as i32,
+        mismatch: format!("Received unexpected message {}", index + 1),
+        content_mismatches: vec![]
+      });
+    }
+
+    Ok(Response::new(proto::CompareStreamResponse {
+      error: String::default(),
+      mismatches
+    }))
+  }
+
   // Request to configure the interaction with CSV contents
   // Example definition we should receive:
   // "column:1", "matching(type,'Name')",
@@ -180,6 +296,10 @@ impl PactPlugin for CsvPactPlugin {
     request: tonic::Request<proto::GenerateContentRequest>,
   ) -> Result<tonic::Response<proto::GenerateContentResponse>, tonic::Status> {
     debug!("Received generate_content request");
+    // Return the content type hint we were given, so CSV sent as binary content types stays consistent
+    let content_type_hint = request.get_ref().contents.as_ref()
+      .map(|contents| contents.content_type_hint)
+      .unwrap_or(ContentTypeHint::Default as i32);
     generate_csv_content(&request)
       .map(|contents| {
         debug!("Generated contents: {}", contents);
@@ -187,12 +307,140 @@ impl PactPlugin for CsvPactPlugin {
           contents: Some(proto::Body {
             content_type: contents.content_type().unwrap_or(ContentType::from("text/csv")).to_string(),
             content: Some(contents.value().unwrap().to_vec()),
-            content_type_hint: ContentTypeHint::Default as i32
+            content_type_hint
           })
         })
       })
       .map_err(|err| tonic::Status::aborted(format!("Failed to generate CSV contents: {}", err)))
   }
+
+  // Request to apply one of the matching rules provided by the plugin
+  async fn match_value(
+    &self,
+    request: tonic::Request<proto::MatchValueRequest>,
+  ) -> Result<tonic::Response<proto::MatchValueResponse>, tonic::Status> {
+    let request = request.get_ref();
+    debug!("Received match_value request for '{}'", request.name);
+    Ok(Response::new(match match_value(request) {
+      Ok(mismatches) => proto::MatchValueResponse {
+        error: String::default(),
+        mismatches
+      },
+      Err(err) => proto::MatchValueResponse {
+        error: err.to_string(),
+        mismatches: vec![]
+      }
+    }))
+  }
+
+  // The CSV plugin does not provide any value generators
+  async fn generate_value(
+    &self,
+    request: tonic::Request<proto::GenerateValueRequest>,
+  ) -> Result<tonic::Response<proto::GenerateValueResponse>, tonic::Status> {
+    let request = request.get_ref();
+    debug!("Received generate_value request for '{}'", request.name);
+    Ok(Response::new(proto::GenerateValueResponse {
+      error: format!("'{}' is not a generator supported by the CSV plugin", request.name),
+      value: None
+    }))
+  }
+
+  // The CSV plugin does not provide a mock server
+  async fn start_mock_server(
+    &self,
+    _request: tonic::Request<proto::StartMockServerRequest>,
+  ) -> Result<tonic::Response<proto::StartMockServerResponse>, tonic::Status> {
+    Ok(Response::new(proto::StartMockServerResponse {
+      response: Some(proto::start_mock_server_response::Response::Error(
+        "The CSV plugin does not provide a mock server".to_string()))
+    }))
+  }
+
+  // The CSV plugin does not provide a mock server
+  async fn shutdown_mock_server(
+    &self,
+    _request: tonic::Request<proto::ShutdownMockServerRequest>,
+  ) -> Result<tonic::Response<proto::ShutdownMockServerResponse>, tonic::Status> {
+    Err(tonic::Status::unimplemented("The CSV plugin does not provide a mock server"))
+  }
+
+  // The CSV plugin does not provide a mock server
+  async fn get_mock_server_results(
+    &self,
+    _request: tonic::Request<proto::MockServerRequest>,
+  ) -> Result<tonic::Response<proto::MockServerResults>, tonic::Status> {
+    Err(tonic::Status::unimplemented("The CSV plugin does not provide a mock server"))
+  }
+
+  // The CSV plugin does not provide a transport, so can not verify interactions
+  async fn prepare_interaction_for_verification(
+    &self,
+    _request: tonic::Request<proto::VerificationPreparationRequest>,
+  ) -> Result<tonic::Response<proto::VerificationPreparationResponse>, tonic::Status> {
+    Ok(Response::new(proto::VerificationPreparationResponse {
+      response: Some(proto::verification_preparation_response::Response::Error(
+        "The CSV plugin does not provide a transport to verify interactions with".to_string()))
+    }))
+  }
+
+  // The CSV plugin does not provide a transport, so can not verify interactions
+  async fn verify_interaction(
+    &self,
+    _request: tonic::Request<proto::VerifyInteractionRequest>,
+  ) -> Result<tonic::Response<proto::VerifyInteractionResponse>, tonic::Status> {
+    Ok(Response::new(proto::VerifyInteractionResponse {
+      response: Some(proto::verify_interaction_response::Response::Error(
+        "The CSV plugin does not provide a transport to verify interactions with".to_string()))
+    }))
+  }
+}
+
+/// Matching rule to apply to a column
+#[derive(Clone, Debug)]
+enum ColumnRule {
+  /// Matching rule supported by the Pact models
+  Core(MatchingRule),
+  /// Matching rule provided by this plugin (i.e. csv-length), with the values for the rule
+  Plugin(String, Value)
+}
+
+/// Mismatches for a message in a stream. Errors and content type mismatches are treated as
+/// mismatches, so the message does not count as being received.
+fn stream_content_mismatches(response: &proto::CompareContentsResponse) -> Vec<proto::ContentMismatch> {
+  if !response.error.is_empty() {
+    vec![proto::ContentMismatch {
+      mismatch: response.error.clone(),
+      .. proto::ContentMismatch::default()
+    }]
+  } else if let Some(type_mismatch) = &response.type_mismatch {
+    vec![proto::ContentMismatch {
+      expected: Some(type_mismatch.expected.clone().into_bytes()),
+      actual: Some(type_mismatch.actual.clone().into_bytes()),
+      mismatch: format!("Expected content type '{}' but got '{}'", type_mismatch.expected, type_mismatch.actual),
+      .. proto::ContentMismatch::default()
+    }]
+  } else {
+    response.results.values()
+      .flat_map(|result| result.mismatches.clone())
+      .collect_vec()
+  }
+}
+
+fn column_rule(rule: &proto::MatchingRule) -> anyhow::Result<ColumnRule> {
+  let mut values = match rule.values.as_ref().map(to_object) {
+    Some(Value::Object(map)) => map,
+    _ => Map::new()
+  };
+  if rule.r#type == CSV_LENGTH_MATCHER {
+    Ok(ColumnRule::Plugin(rule.r#type.clone(), Value::Object(values)))
+  } else {
+    values.insert("match".to_string(), Value::String(rule.r#type.clone()));
+    debug!("Creating matching rule with {:?}", values);
+    MatchingRule::from_json(&Value::Object(values))
+      .map(ColumnRule::Core)
+      .map_err(|err| anyhow!("Matching rule '{}' is not valid: {}", rule.r#type, err))
+  }
 }
 
 fn compare_contents<R: Read>(
@@ -200,7 +448,7 @@ fn compare_contents<R: Read>(
   expected: &mut Reader<R>,
   actual: &mut Reader<R>,
   allow_unexpected_keys: bool,
-  rules: HashMap<String, RuleList>
+  rules: HashMap<String, Vec<ColumnRule>>
 ) -> anyhow::Result<tonic::Response<proto::CompareContentsResponse>> {
   debug!("Comparing contents using allow_unexpected_keys ({}) and rules ({:?})", allow_unexpected_keys, rules);
 
@@ -284,18 +532,21 @@ fn compare_contents<R: Read>(
       String::default() => proto::ContentMismatches {
         mismatches: results
       }
-    }
+    },
+    .. proto::CompareContentsResponse::default()
   }))
 }
 
 fn compare_row(
   expected_row: &StringRecord,
   actual_row: &StringRecord,
-  rules: &HashMap<String, RuleList>,
+  rules: &HashMap<String, Vec<ColumnRule>>,
   has_headers: bool,
   expected_headers: &StringRecord,
   actual_headers: &HashMap<&str, usize>,
   results: &mut Vec<proto::ContentMismatch>) {
+  let row_start = results.len();
+  let mut column_diffs = vec![];
   for (index, expected_item) in expected_row.iter().enumerate() {
     let header = expected_headers.get(index).unwrap_or_default();
     let item = if has_headers {
@@ -309,14 +560,23 @@ fn compare_row(
 
     let path = format!("column:{}", index + 1);
     let header_path = format!("column:{}", header);
+    let mismatch_count = results.len();
 
     if let Some(rules) = rules.get(&path).or_else(|| rules.get(header_path.as_str())) {
-      for rule in &rules.rules {
-        if let Err(err) = expected_item.matches_with(item, rule, false) {
+      for rule in rules {
+        let mismatches = match rule {
+          ColumnRule::Core(rule) => expected_item.matches_with(item, rule, false)
+            .err()
+            .map(|err| vec![err.to_string()])
+            .unwrap_or_default(),
+          ColumnRule::Plugin(name, values) => apply_matching_rule(name, values, item)
+            .unwrap_or_else(|err| vec![err.to_string()])
+        };
+        for mismatch in mismatches {
           results.push(proto::ContentMismatch {
             expected: Some(expected_item.as_bytes().to_vec()),
             actual: Some(item.as_bytes().to_vec()),
-            mismatch: err.to_string(),
+            mismatch,
             path: format!("row:{:5}, column:{:2}", actual_row.position().unwrap().line(), index),
             diff: String::default()
           });
@@ -331,6 +591,17 @@ fn compare_row(
         diff: String::default()
       });
     }
+
+    if results.len() > mismatch_count {
+      column_diffs.push(format!("  column {}: '{}' -> '{}'", index + 1, expected_item, item));
+    }
+  }
+
+  // Add a diff of the row to the first mismatch for the row, so the row is only displayed once
+  if let Some(mismatch) = results.get_mut(row_start) {
+    let line = actual_row.position().map(|pos| pos.line()).unwrap_or_default();
+    mismatch.diff = format!("-row {}: {}\n+row {}: {}\n{}", line, expected_row.iter().join(","),
+      line, actual_row.iter().join(","), column_diffs.join("\n"));
   }
 }
 
@@ -351,6 +622,7 @@ impl Stream for TcpIncoming {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
   let env = Env::new().filter("LOG_LEVEL");
   env_logger::init_from_env(env);
+  telemetry::init_telemetry();
 
   let addr: SocketAddr = "0.0.0.0:0".parse()?;
   let listener = TcpListener::bind(addr).await?;
@@ -362,8 +634,198 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   let plugin = CsvPactPlugin::default();
   Server::builder()
+    .trace_fn(telemetry::request_span)
     .add_service(PactPluginServer::new(plugin))
     .serve_with_incoming(TcpIncoming { inner: listener }).await?;
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use csv::ReaderBuilder;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::proto;
+  use crate::proto::interaction_response::MessageDirection;
+  use crate::proto::pact_plugin_server::PactPlugin;
+  use crate::utils::to_value;
+
+  use super::{compare_contents, CsvPactPlugin, stream_content_mismatches};
+
+  #[test]
+  fn compare_contents_adds_a_row_diff_to_the_first_mismatch_for_the_row() {
+    let mut expected = ReaderBuilder::new().has_headers(false).from_reader("a,b,c\n".as_bytes());
+    let mut actual = ReaderBuilder::new().has_headers(false).from_reader("a,x,y\n".as_bytes());
+
+    let response = compare_contents(false, &mut expected, &mut actual, false, hashmap!{}).unwrap();
+
+    let mismatches = &response.get_ref().results.get("").unwrap().mismatches;
+    expect!(mismatches.len()).to(be_equal_to(2));
+    expect!(mismatches[0].diff.as_str())
+      .to(be_equal_to("-row 1: a,b,c\n+row 1: a,x,y\n  column 2: 'b' -> 'x'\n  column 3: 'c' -> 'y'"));
+    expect!(mismatches[1].diff.as_str()).to(be_equal_to(""));
+  }
+
+  fn compare_contents_request(rule_type: &str, values: serde_json::Value) -> proto::CompareContentsRequest {
+    proto::CompareContentsRequest {
+      expected: csv_body("abc,1"),
+      actual: csv_body("abcdef,1"),
+      rules: hashmap! {
+        "column:1".to_string() => proto::MatchingRules {
+          rule: vec![
+            proto::MatchingRule {
+              r#type: rule_type.to_string(),
+              values: Some(to_proto_struct(values))
+            }
+          ]
+        }
+      },
+      .. proto::CompareContentsRequest::default()
+    }
+  }
+
+  fn to_proto_struct(values: serde_json::Value) -> prost_types::Struct {
+    prost_types::Struct {
+      fields: values.as_object().unwrap().iter().map(|(k, v)| (k.clone(), to_value(v))).collect()
+    }
+  }
+
+  #[tokio::test]
+  async fn compare_contents_applies_the_csv_length_matching_rule() {
+    let request = compare_contents_request("csv-length", json!({ "max": 4 }));
+
+    let response = CsvPactPlugin {}.compare_contents(tonic::Request::new(request)).await.unwrap().into_inner();
+
+    expect!(response.error.as_str()).to(be_equal_to(""));
+    let mismatches = &response.results.get("").unwrap().mismatches;
+    expect!(mismatches.len()).to(be_equal_to(1));
+    expect!(mismatches[0].mismatch.as_str()).to(be_equal_to("Expected 'abcdef' to have a length of at most 4, but was 6"));
+  }
+
+  #[tokio::test]
+  async fn compare_contents_returns_an_error_for_invalid_matching_rules() {
+    let request = compare_contents_request("not-a-rule", json!({}));
+
+    let response = CsvPactPlugin {}.compare_contents(tonic::Request::new(request)).await.unwrap().into_inner();
+
+    expect!(response.error.starts_with("Matching rule 'not-a-rule' is not valid")).to(be_true());
+  }
+
+  fn csv_body(contents: &str) -> Option<proto::Body> {
+    Some(proto::Body {
+      content_type: "text/csv".to_string(),
+      content: Some(format!("a,b\n{}\n", contents).into_bytes()),
+      content_type_hint: 0
+    })
+  }
+
+  #[tokio::test]
+  async fn compare_stream_applies_the_message_counts() {
+    let request = proto::CompareStreamRequest {
+      expected: vec![
+        proto::ExpectedStreamMessage {
+          contents: csv_body("1,2"),
+          direction: MessageDirection::ConsumerToProvider as i32,
+          .. proto::ExpectedStreamMessage::default()
+        },
+        proto::ExpectedStreamMessage {
+          contents: csv_body("3,4"),
+          direction: MessageDirection::ProviderToConsumer as i32,
+          count: Some(proto::StreamCount { min: 2, max: 3 }),
+          .. proto::ExpectedStreamMessage::default()
+        }
+      ],
+      actual: vec![
+        proto::StreamMessage { contents: csv_body("1,2"), direction: MessageDirection::ConsumerToProvider as i32 },
+        proto::StreamMessage { contents: csv_body("3,4"), direction: MessageDirection::ProviderToConsumer as i32 },
+        proto::StreamMessage { contents: csv_body("3,5"), direction: MessageDirection::ProviderToConsumer as i32 },
+        proto::StreamMessage { contents: csv_body("1,2"), direction: MessageDirection::ConsumerToProvider as i32 }
+      ],
+      .. proto::CompareStreamRequest::default()
+    };
+
+    let response = CsvPactPlugin {}.compare_stream(tonic::Request::new(request)).await.unwrap().into_inner();
+
+    expect!(response.mismatches.len()).to(be_equal_to(2));
+    expect!(response.mismatches[0].expected_index).to(be_equal_to(1));
+    expect!(response.mismatches[0].actual_index).to(be_equal_to(2));
+    expect!(response.mismatches[0].content_mismatches.is_empty()).to(be_false());
+    expect!(response.mismatches[1].expected_index).to(be_equal_to(2));
+    expect!(response.mismatches[1].actual_index).to(be_equal_to(3));
+  }
+
+  #[tokio::test]
+  async fn compare_stream_treats_an_error_comparing_a_message_as_a_mismatch() {
+    let request = proto::CompareStreamRequest {
+      expected: vec![
+        proto::ExpectedStreamMessage {
+          contents: csv_body("1,2"),
+          direction: MessageDirection::ConsumerToProvider as i32,
+          rules: hashmap! {
+            "column:1".to_string() => proto::MatchingRules {
+              rule: vec![ proto::MatchingRule { r#type: "not-a-rule".to_string(), values: None } ]
+            }
+          },
+          .. proto::ExpectedStreamMessage::default()
+        }
+      ],
+      actual: vec![
+        proto::StreamMessage { contents: csv_body("1,2"), direction: MessageDirection::ConsumerToProvider as i32 }
+      ],
+      .. proto::CompareStreamRequest::default()
+    };
+
+    let response = CsvPactPlugin {}.compare_stream(tonic::Request::new(request)).await.unwrap().into_inner();
+
+    expect!(response.mismatches.len()).to(be_equal_to(1));
+    expect!(response.mismatches[0].actual_index).to(be_equal_to(0));
+    let content_mismatches = &response.mismatches[0].content_mismatches;
+    expect!(content_mismatches.len()).to(be_equal_to(1));
+    expect!(content_mismatches[0].mismatch.starts_with("Matching rule 'not-a-rule' is not valid")).to(be_true());
+  }
+
+  #[tokio::test]
+  async fn compare_stream_treats_malformed_csv_as_a_mismatch() {
+    let request = proto::CompareStreamRequest {
+      expected: vec![
+        proto::ExpectedStreamMessage {
+          contents: csv_body("1,2"),
+          direction: MessageDirection::ConsumerToProvider as i32,
+          .. proto::ExpectedStreamMessage::default()
+        }
+      ],
+      actual: vec![
+        proto::StreamMessage { contents: csv_body("1,2,3"), direction: MessageDirection::ConsumerToProvider as i32 }
+      ],
+      .. proto::CompareStreamRequest::default()
+    };
+
+    let response = CsvPactPlugin {}.compare_stream(tonic::Request::new(request)).await.unwrap().into_inner();
+
+    expect!(response.mismatches.len()).to(be_equal_to(1));
+    expect!(response.mismatches[0].actual_index).to(be_equal_to(0));
+    let content_mismatches = &response.mismatches[0].content_mismatches;
+    expect!(content_mismatches.len()).to(be_equal_to(1));
+    expect!(content_mismatches[0].mismatch.starts_with("Failed to compare CSV contents")).to(be_true());
+  }
+
+  #[test]
+  fn stream_content_mismatches_treats_a_content_type_mismatch_as_a_mismatch() {
+    let response = proto::CompareContentsResponse {
+      type_mismatch: Some(proto::ContentTypeMismatch {
+        expected: "text/csv".to_string(),
+        actual: "application/json".to_string()
+      }),
+      .. proto::CompareContentsResponse::default()
+    };
+
+    let mismatches = stream_content_mismatches(&response);
+
+    expect!(mismatches.len()).to(be_equal_to(1));
+    expect!(mismatches[0].mismatch.as_str())
+      .to(be_equal_to("Expected content type 'text/csv' but got 'application/json'"));
+  }
+}
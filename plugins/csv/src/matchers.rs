@@ -0,0 +1,56 @@
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::proto;
+use crate::proto::{to_object, to_value};
+
+/// Name of the sample matching rule provided by the plugin. It checks that the length of a column
+/// value is within the `min` and `max` values (both optional).
+pub const CSV_LENGTH_MATCHER: &str = "csv-length";
+
+pub fn match_value(request: &proto::MatchValueRequest) -> anyhow::Result<Vec<String>> {
+  let values = request.values.as_ref().map(to_object).unwrap_or(Value::Null);
+  let actual = match request.actual.as_ref().map(to_value) {
+    Some(Value::String(s)) => s,
+    Some(Value::Null) | None => String::default(),
+    Some(value) => value.to_string()
+  };
+  apply_matching_rule(request.name.as_str(), &values, actual.as_str())
+}
+
+/// Applies one of the matching rules provided by the plugin to the actual value, returning the
+/// mismatches
+pub fn apply_matching_rule(name: &str, values: &Value, actual: &str) -> anyhow::Result<Vec<String>> {
+  match name {
+    CSV_LENGTH_MATCHER => {
+      let min = length_value(values, "min")?;
+      let max = length_value(values, "max")?;
+
+      let length = actual.chars().count();
+      let mut mismatches = vec![];
+      if let Some(min) = min {
+        if length < min {
+          mismatches.push(format!("Expected '{}' to have a length of at least {}, but was {}", actual, min, length));
+        }
+      }
+      if let Some(max) = max {
+        if length > max {
+          mismatches.push(format!("Expected '{}' to have a length of at most {}, but was {}", actual, max, length));
+        }
+      }
+      Ok(mismatches)
+    }
+    _ => Err(anyhow!("'{}' is not a matching rule supported by the CSV plugin", name))
+  }
+}
+
+fn length_value(values: &Value, name: &str) -> anyhow::Result<Option<usize>> {
+  match values.get(name) {
+    Some(Value::Number(n)) => n.as_f64()
+      .filter(|n| *n >= 0.0)
+      .map(|n| Some(n as usize))
+      .ok_or_else(|| anyhow!("'{}' is not a valid value for {}", n, name)),
+    Some(Value::Null) | None => Ok(None),
+    Some(value) => Err(anyhow!("'{}' is not a valid value for {}", value, name))
+  }
+}
@@ -0,0 +1,32 @@
+//! Exports trace spans for the requests from the Pact framework, continuing the trace from the
+//! driver. This uses the telemetry support from the driver, with the same environment variables
+//! (`PACT_PLUGIN_OTLP_ENDPOINT` or `PACT_PLUGIN_TRACE_FILE`), which are passed through to the
+//! plugin process. The spans are only exported if the plugin is built with the `otel` feature.
+
+#[cfg(feature = "otel")] use log::warn;
+use pact_plugin_driver::telemetry::set_parent_from_headers;
+#[cfg(feature = "otel")] use pact_plugin_driver::telemetry::{self, TelemetryExporter};
+use tonic::codegen::http::Request;
+use tracing::{info_span, Span};
+
+/// Sets up exporting the trace spans if one of the environment variables is set. Must be called
+/// from within the Tokio runtime.
+#[cfg(feature = "otel")]
+pub fn init_telemetry() {
+  if let Some(exporter) = TelemetryExporter::from_env() {
+    if let Err(err) = telemetry::init_telemetry(exporter, "pact-plugin-csv") {
+      warn!("Could not set up exporting trace spans - {}", err);
+    }
+  }
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init_telemetry() {}
+
+/// Span for a request from the Pact framework, with the trace context from the request headers
+/// as the parent
+pub fn request_span(request: &Request<()>) -> Span {
+  let span = info_span!("csv_plugin", rpc = %request.uri().path());
+  set_parent_from_headers(&span, request.headers());
+  span
+}
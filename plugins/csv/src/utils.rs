@@ -25,7 +25,12 @@ pub fn to_value(value: &Value) -> prost_types::Value {
 pub fn from_value(value: &prost_types::Value) -> Value {
   match value.kind.as_ref().unwrap() {
     Kind::NullValue(_) => Value::Null,
-    Kind::NumberValue(n) => json!(*n),
+    // Protobuf only has doubles, so convert whole numbers back to integers (i.e. generator min/max values)
+    Kind::NumberValue(n) => if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+      json!(*n as i64)
+    } else {
+      json!(*n)
+    },
     Kind::StringValue(s) => Value::String(s.clone()),
     Kind::BoolValue(b) => Value::Bool(*b),
     Kind::StructValue(s) => Value::Object(s.fields.iter()